@@ -0,0 +1,74 @@
+use anchor_lang::{AccountDeserialize, Discriminator};
+use clap::{App, Arg};
+use serum_saver_cli::{load_saver_info, load_saver_market_info, CliResult};
+use serum_saver_client::{
+    inspect::OpenOrdersInfo,
+    serum_saver::{self, Saver, SaverMarket},
+};
+use solana_cli_config::{Config, CONFIG_FILE};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+fn main() -> CliResult {
+    let matches = App::new("serum-saver-inspect")
+        .about("Print Saver, SaverMarket or saver OpenOrders account")
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .short("u")
+                .value_name("URL")
+                .takes_value(true)
+                .help("JSON RPC URL, from Solana CLI config if not specified"),
+        )
+        .arg(
+            Arg::with_name("saver")
+                .long("saver")
+                .value_name("ADDRESS")
+                .takes_value(true)
+                .help("Saver of inspected OpenOrders, required for OpenOrders"),
+        )
+        .arg(
+            Arg::with_name("address")
+                .value_name("ADDRESS")
+                .required(true),
+        )
+        .get_matches();
+
+    let url = match matches.value_of("url") {
+        Some(url) => url.to_owned(),
+        None => {
+            let config_path = CONFIG_FILE
+                .as_ref()
+                .ok_or("Unable to determine a config file")?;
+            Config::load(config_path)?.json_rpc_url
+        }
+    };
+    let rpc = RpcClient::new(url);
+    let address: Pubkey = matches
+        .value_of("address")
+        .ok_or("`address` is not specified")?
+        .parse()?;
+
+    let account = rpc.get_account(&address)?;
+    if account.owner != serum_saver::ID {
+        let saver: Pubkey = matches
+            .value_of("saver")
+            .ok_or("`saver` is required for OpenOrders")?
+            .parse()?;
+        let saver = Saver::try_deserialize(&mut rpc.get_account_data(&saver)?.as_slice())?;
+        let info = OpenOrdersInfo::decode_saver_open_orders(
+            address,
+            &account.owner,
+            &account.data,
+            &saver,
+        )?;
+        println!("{}", info);
+    } else if account.data.starts_with(&Saver::discriminator()) {
+        println!("{}", load_saver_info(&rpc, address, &account.data)?);
+    } else if account.data.starts_with(&SaverMarket::discriminator()) {
+        println!("{}", load_saver_market_info(&rpc, address, &account.data)?);
+    } else {
+        return Err("Unknown serum-saver account".into());
+    }
+    Ok(())
+}
@@ -0,0 +1,358 @@
+use anchor_lang::AccountDeserialize;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use serum_dex::{fees::FeeTier, instruction::SelfTradeBehavior, matching::Side};
+use serum_saver_client::{
+    inspect::{SaverInfo, SaverMarketInfo},
+    serum_saver::{Saver, SaverMarket},
+    SwapParams,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::{error::Error, str::FromStr};
+
+pub type CliError = Box<dyn Error>;
+pub type CliResult<T = ()> = Result<T, CliError>;
+
+/// Command-line interface of `serum-saver`.
+pub fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("serum-saver")
+        .about("Serum fee saver command-line tool")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .short("C")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Configuration file to use, same format as Solana CLI config"),
+        )
+        .subcommand(
+            SubCommand::with_name("init-saver")
+                .about("Create SRM (or MSRM) vault and initialize new saver")
+                .arg(keypair_arg(
+                    "saver",
+                    "Saver keypair, random if not specified",
+                ))
+                .arg(pubkey_arg(
+                    "dex-program",
+                    "DEX program of saver markets, serum-dex mainnet if not specified",
+                ))
+                .arg(
+                    Arg::with_name("msrm")
+                        .long("msrm")
+                        .help("Create MSRM vault instead of SRM vault"),
+                )
+                .arg(fee_tier_arg().required(false).default_value("base")),
+        )
+        .subcommand(
+            SubCommand::with_name("set-min-fee-tier")
+                .about("Set minimum fee tier required for swaps, payer should be saver authority")
+                .arg(pubkey_arg("saver", "Saver address").required(true))
+                .arg(fee_tier_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("set-allowlist")
+                .about("Enable or disable wallet allowlist, payer should be saver authority")
+                .arg(pubkey_arg("saver", "Saver address").required(true))
+                .arg(
+                    Arg::with_name("enabled")
+                        .long("enabled")
+                        .takes_value(true)
+                        .possible_values(&["true", "false"])
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("allow")
+                .about("Add wallet to saver allowlist, payer should be saver authority")
+                .arg(pubkey_arg("saver", "Saver address").required(true))
+                .arg(pubkey_arg("wallet", "Wallet owner address").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("disallow")
+                .about("Remove wallet from saver allowlist, payer should be saver authority")
+                .arg(pubkey_arg("saver", "Saver address").required(true))
+                .arg(pubkey_arg("wallet", "Wallet owner address").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("init-market")
+                .about("Initialize saver market for Serum market")
+                .arg(pubkey_arg("saver", "Saver address").required(true))
+                .arg(pubkey_arg("market", "Serum market address").required(true))
+                .arg(keypair_arg(
+                    "saver-market",
+                    "Saver market keypair, random if not specified",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("set-market-limits")
+                .about("Set swap limits of saver market, zero means no limit")
+                .arg(pubkey_arg("saver-market", "Saver market address").required(true))
+                .arg(amount_arg(
+                    "max-notional",
+                    "Max native pc quantity of one swap without fees",
+                ))
+                .arg(amount_arg(
+                    "max-swaps-per-slot",
+                    "Max swaps through saver market in one slot",
+                ))
+                .arg(amount_arg(
+                    "max-daily-volume",
+                    "Max native pc quantity swapped by one wallet in a day",
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("set-market-oracle")
+                .about("Set Pyth price account and price band of saver market")
+                .arg(pubkey_arg("saver-market", "Saver market address").required(true))
+                .arg(pubkey_arg(
+                    "oracle",
+                    "Pyth price account, disable oracle check if not specified",
+                ))
+                .arg(amount_arg(
+                    "band-bps",
+                    "Max deviation of swap price from oracle price in basis points",
+                ))
+                .arg(
+                    amount_arg(
+                        "max-age-slots",
+                        "Max slots since oracle price was published",
+                    )
+                    .required(false)
+                    .default_value("25"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("set-crank-tip")
+                .about("Set tip paid from saver market fees for consumed events")
+                .arg(pubkey_arg("saver-market", "Saver market address").required(true))
+                .arg(amount_arg("tip", "Native pc quantity paid for one crank")),
+        )
+        .subcommand(
+            SubCommand::with_name("crank")
+                .about("Consume events of saver market OpenOrders and collect tip")
+                .arg(pubkey_arg("saver-market", "Saver market address").required(true))
+                .arg(pubkey_arg(
+                    "tip-account",
+                    "Pc token account for tip, payer associated pc wallet (created if missing) \
+                     if not specified",
+                ))
+                .arg(
+                    amount_arg("limit", "Max number of consumed events")
+                        .required(false)
+                        .default_value("65535"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("settle")
+                .about("Settle free funds of saver market OpenOrders to market fees")
+                .arg(pubkey_arg("saver-market", "Saver market address").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("withdraw-fees")
+                .about(
+                    "Withdraw saver market fees to payer wallets, payer should be saver authority",
+                )
+                .arg(pubkey_arg("saver-market", "Saver market address").required(true))
+                .arg(
+                    amount_arg("coin-amount", "Native coin quantity")
+                        .required(false)
+                        .default_value("0"),
+                )
+                .arg(
+                    amount_arg("pc-amount", "Native pc quantity")
+                        .required(false)
+                        .default_value("0"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("init-user-volume")
+                .about("Initialize daily volume tracking of payer on saver market")
+                .arg(pubkey_arg("saver-market", "Saver market address").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("swap")
+                .about("Swap through saver market with payer associated token accounts")
+                .arg(pubkey_arg("saver-market", "Saver market address").required(true))
+                .arg(
+                    Arg::with_name("side")
+                        .long("side")
+                        .takes_value(true)
+                        .possible_values(&["bid", "ask"])
+                        .required(true),
+                )
+                .arg(amount_arg(
+                    "limit-price",
+                    "Limit price in pc lots per coin lot",
+                ))
+                .arg(amount_arg("max-coin-qty", "Max coin quantity in lots"))
+                .arg(amount_arg(
+                    "max-pc-qty",
+                    "Max native pc quantity including fees",
+                ))
+                .arg(
+                    amount_arg("limit", "Max number of matched orders")
+                        .required(false)
+                        .default_value("65535"),
+                )
+                .arg(
+                    amount_arg(
+                        "max-price-impact-bps",
+                        "Max distance of matched orders from the best price, 0 for no cap",
+                    )
+                    .required(false)
+                    .default_value("0"),
+                )
+                .arg(
+                    Arg::with_name("self-trade-behavior")
+                        .long("self-trade-behavior")
+                        .value_name("BEHAVIOR")
+                        .takes_value(true)
+                        .possible_values(&["decrement-take", "cancel-provide", "abort-transaction"])
+                        .default_value("abort-transaction")
+                        .help("Serum self-trade behavior"),
+                )
+                .arg(
+                    amount_arg("client-order-id", "Client order id passed to serum")
+                        .required(false)
+                        .default_value("0"),
+                )
+                .arg(pubkey_arg(
+                    "owner",
+                    "Swap from owner wallets, which approved payer as delegate",
+                ))
+                .arg(
+                    Arg::with_name("user-stats")
+                        .long("user-stats")
+                        .help("Update UserStats of wallet owner, should be initialized before"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("show-saver")
+                .about("Show saver state")
+                .arg(Arg::with_name("saver").value_name("ADDRESS").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("show-market")
+                .about("Show saver market state")
+                .arg(
+                    Arg::with_name("saver-market")
+                        .value_name("ADDRESS")
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("list-markets")
+                .about("List saver markets of saver")
+                .arg(Arg::with_name("saver").value_name("ADDRESS").required(true)),
+        )
+}
+
+pub fn keypair_arg<'a, 'b>(name: &'a str, help: &'b str) -> Arg<'a, 'b> {
+    Arg::with_name(name)
+        .long(name)
+        .value_name("KEYPAIR")
+        .takes_value(true)
+        .help(help)
+}
+
+pub fn pubkey_arg<'a, 'b>(name: &'a str, help: &'b str) -> Arg<'a, 'b> {
+    Arg::with_name(name)
+        .long(name)
+        .value_name("ADDRESS")
+        .takes_value(true)
+        .help(help)
+}
+
+pub fn amount_arg<'a, 'b>(name: &'a str, help: &'b str) -> Arg<'a, 'b> {
+    Arg::with_name(name)
+        .long(name)
+        .value_name("AMOUNT")
+        .takes_value(true)
+        .required(true)
+        .help(help)
+}
+
+pub fn fee_tier_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("min-fee-tier")
+        .long("min-fee-tier")
+        .value_name("TIER")
+        .takes_value(true)
+        .possible_values(&["base", "srm2", "srm3", "srm4", "srm5", "srm6", "msrm"])
+        .required(true)
+        .help("Minimum Serum fee tier of saver SRM vault")
+}
+
+pub fn fee_tier_of(matches: &ArgMatches<'_>) -> CliResult<FeeTier> {
+    Ok(match matches.value_of("min-fee-tier") {
+        Some("base") => FeeTier::Base,
+        Some("srm2") => FeeTier::SRM2,
+        Some("srm3") => FeeTier::SRM3,
+        Some("srm4") => FeeTier::SRM4,
+        Some("srm5") => FeeTier::SRM5,
+        Some("srm6") => FeeTier::SRM6,
+        Some("msrm") => FeeTier::MSRM,
+        _ => return Err("`min-fee-tier` is not specified".into()),
+    })
+}
+
+pub fn value_of<T>(matches: &ArgMatches<'_>, name: &str) -> CliResult<T>
+where
+    T: FromStr,
+    T::Err: Error + 'static,
+{
+    let value = matches
+        .value_of(name)
+        .ok_or_else(|| format!("`{}` is not specified", name))?;
+    Ok(value.parse()?)
+}
+
+/// Swap parameters of `swap` subcommand.
+pub fn swap_params_of(matches: &ArgMatches<'_>) -> CliResult<SwapParams> {
+    Ok(SwapParams {
+        side: match matches.value_of("side") {
+            Some("bid") => Side::Bid,
+            Some("ask") => Side::Ask,
+            _ => return Err("`side` is not specified".into()),
+        },
+        limit_price: value_of(matches, "limit-price")?,
+        max_coin_qty: value_of(matches, "max-coin-qty")?,
+        max_native_pc_qty_including_fees: value_of(matches, "max-pc-qty")?,
+        limit: value_of(matches, "limit")?,
+        max_price_impact_bps: value_of(matches, "max-price-impact-bps")?,
+        self_trade_behavior: match matches.value_of("self-trade-behavior") {
+            Some("decrement-take") => SelfTradeBehavior::DecrementTake,
+            Some("cancel-provide") => SelfTradeBehavior::CancelProvide,
+            Some("abort-transaction") => SelfTradeBehavior::AbortTransaction,
+            _ => return Err("`self-trade-behavior` is not specified".into()),
+        },
+        client_order_id: value_of(matches, "client-order-id")?,
+    })
+}
+
+/// Saver with its SRM vault, `data` is data of saver account.
+pub fn load_saver_info(rpc: &RpcClient, address: Pubkey, data: &[u8]) -> CliResult<SaverInfo> {
+    let state = Saver::try_deserialize(&mut &data[..])?;
+    let srm_vault_data = rpc.get_account_data(&state.srm_vault)?;
+    Ok(SaverInfo::decode(address, data, &srm_vault_data)?)
+}
+
+/// Saver market with its vaults and OpenOrders, `data` is data of saver market account.
+pub fn load_saver_market_info(
+    rpc: &RpcClient,
+    address: Pubkey,
+    data: &[u8],
+) -> CliResult<SaverMarketInfo> {
+    let state = SaverMarket::try_deserialize(&mut &data[..])?;
+    let coin_vault_data = rpc.get_account_data(&state.coin_vault)?;
+    let pc_vault_data = rpc.get_account_data(&state.pc_vault)?;
+    let open_orders_data = rpc.get_account_data(&state.open_orders)?;
+    Ok(SaverMarketInfo::decode(
+        address,
+        data,
+        &coin_vault_data,
+        &pc_vault_data,
+        &open_orders_data,
+    )?)
+}
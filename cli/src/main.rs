@@ -0,0 +1,399 @@
+use anchor_lang::{AccountDeserialize, Discriminator};
+use clap::ArgMatches;
+use serum_dex::instruction::{msrm_token, srm_token};
+use serum_saver_cli::{
+    app, fee_tier_of, load_saver_info, load_saver_market_info, swap_params_of, value_of, CliResult,
+};
+use serum_saver_client::{
+    add_to_allowlist, create_open_orders, create_srm_vault, initialize_market, initialize_saver,
+    load_market_state, remove_from_allowlist,
+    serum_saver::{self, Saver, SaverMarket},
+    set_allowlist_enabled, set_min_fee_tier, DexMarket, SaverMarketAccounts, SwapWallet,
+    OPEN_ORDERS_ACCOUNT_SIZE,
+};
+use solana_account_decoder::UiAccountEncoding;
+use solana_cli_config::{Config, CONFIG_FILE};
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+use spl_associated_token_account::create_associated_token_account;
+use std::str::FromStr;
+
+fn main() -> CliResult {
+    let matches = app().get_matches();
+
+    let config_path = match matches.value_of("config") {
+        Some(path) => path.to_owned(),
+        None => CONFIG_FILE
+            .as_ref()
+            .ok_or("Unable to determine a config file")?
+            .clone(),
+    };
+    let ctx = CliContext::new(&config_path)?;
+
+    match matches.subcommand() {
+        ("init-saver", Some(matches)) => command_init_saver(&ctx, matches),
+        ("set-min-fee-tier", Some(matches)) => command_set_min_fee_tier(&ctx, matches),
+        ("set-allowlist", Some(matches)) => command_set_allowlist(&ctx, matches),
+        ("allow", Some(matches)) => command_allow(&ctx, matches),
+        ("disallow", Some(matches)) => command_disallow(&ctx, matches),
+        ("init-market", Some(matches)) => command_init_market(&ctx, matches),
+        ("set-market-limits", Some(matches)) => command_set_market_limits(&ctx, matches),
+        ("set-market-oracle", Some(matches)) => command_set_market_oracle(&ctx, matches),
+        ("set-crank-tip", Some(matches)) => command_set_crank_tip(&ctx, matches),
+        ("crank", Some(matches)) => command_crank(&ctx, matches),
+        ("settle", Some(matches)) => command_settle(&ctx, matches),
+        ("withdraw-fees", Some(matches)) => command_withdraw_fees(&ctx, matches),
+        ("init-user-volume", Some(matches)) => command_init_user_volume(&ctx, matches),
+        ("swap", Some(matches)) => command_swap(&ctx, matches),
+        ("show-saver", Some(matches)) => command_show_saver(&ctx, matches),
+        ("show-market", Some(matches)) => command_show_market(&ctx, matches),
+        ("list-markets", Some(matches)) => command_list_markets(&ctx, matches),
+        _ => unreachable!(),
+    }
+}
+
+fn keypair_of(matches: &ArgMatches<'_>, name: &str) -> CliResult<Keypair> {
+    match matches.value_of(name) {
+        Some(path) => read_keypair_file(path),
+        None => Ok(Keypair::new()),
+    }
+}
+
+struct CliContext {
+    rpc: RpcClient,
+    payer: Keypair,
+}
+
+impl CliContext {
+    fn new(config_path: &str) -> CliResult<Self> {
+        let config = Config::load(config_path)?;
+        let commitment = CommitmentConfig::from_str(&config.commitment)?;
+        Ok(Self {
+            rpc: RpcClient::new_with_commitment(config.json_rpc_url, commitment),
+            payer: read_keypair_file(&config.keypair_path)?,
+        })
+    }
+
+    fn process(&self, instructions: &[Instruction], signers: &[&Keypair]) -> CliResult {
+        let (recent_blockhash, _fee_calculator) = self.rpc.get_recent_blockhash()?;
+        let mut signing_keypairs = vec![&self.payer];
+        signing_keypairs.extend_from_slice(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &signing_keypairs,
+            recent_blockhash,
+        );
+        let signature = self
+            .rpc
+            .send_and_confirm_transaction_with_spinner(&transaction)?;
+        println!("Signature: {}", signature);
+        Ok(())
+    }
+
+    fn get_state<T: AccountDeserialize>(&self, key: &Pubkey) -> CliResult<T> {
+        let data = self.rpc.get_account_data(key)?;
+        Ok(T::try_deserialize(&mut data.as_slice())?)
+    }
+
+    fn get_dex_market(&self, market: &Pubkey) -> CliResult<DexMarket> {
+        let account = self.rpc.get_account(market)?;
+        let state = load_market_state(&account.data)?;
+        Ok(DexMarket::new(account.owner, &state)?)
+    }
+
+    fn get_saver_market(&self, saver_market: &Pubkey) -> CliResult<SaverMarketAccounts> {
+        let saver_market_state: SaverMarket = self.get_state(saver_market)?;
+        let saver_state: Saver = self.get_state(&saver_market_state.saver)?;
+        let dex_market = self.get_dex_market(&saver_market_state.market)?;
+        Ok(SaverMarketAccounts {
+            saver: saver_market_state.saver,
+            saver_state,
+            saver_market: *saver_market,
+            saver_market_state,
+            dex_market,
+        })
+    }
+}
+
+fn command_init_saver(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver = keypair_of(matches, "saver")?;
+    let dex_program = match matches.value_of("dex-program") {
+        Some(_) => value_of(matches, "dex-program")?,
+        None => serum_saver::dex::ID,
+    };
+    let srm_mint = if matches.is_present("msrm") {
+        msrm_token::ID
+    } else {
+        srm_token::ID
+    };
+    let min_fee_tier = fee_tier_of(matches)?;
+    let payer = ctx.payer.pubkey();
+
+    ctx.process(
+        &[
+            create_srm_vault(&saver.pubkey(), &srm_mint, &payer),
+            initialize_saver(
+                &saver.pubkey(),
+                &srm_mint,
+                &dex_program,
+                &payer,
+                &payer,
+                min_fee_tier,
+            ),
+        ],
+        &[&saver],
+    )?;
+
+    println!("Saver: {}", saver.pubkey());
+    Ok(())
+}
+
+fn command_set_min_fee_tier(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver: Pubkey = value_of(matches, "saver")?;
+    let min_fee_tier = fee_tier_of(matches)?;
+
+    ctx.process(
+        &[set_min_fee_tier(&saver, &ctx.payer.pubkey(), min_fee_tier)],
+        &[],
+    )
+}
+
+fn command_set_allowlist(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver: Pubkey = value_of(matches, "saver")?;
+    let enabled: bool = value_of(matches, "enabled")?;
+
+    ctx.process(
+        &[set_allowlist_enabled(&saver, &ctx.payer.pubkey(), enabled)],
+        &[],
+    )
+}
+
+fn command_allow(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver: Pubkey = value_of(matches, "saver")?;
+    let wallet: Pubkey = value_of(matches, "wallet")?;
+    let payer = ctx.payer.pubkey();
+
+    ctx.process(&[add_to_allowlist(&saver, &payer, &wallet, &payer)], &[])
+}
+
+fn command_disallow(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver: Pubkey = value_of(matches, "saver")?;
+    let wallet: Pubkey = value_of(matches, "wallet")?;
+
+    ctx.process(
+        &[remove_from_allowlist(&saver, &ctx.payer.pubkey(), &wallet)],
+        &[],
+    )
+}
+
+fn command_init_market(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver: Pubkey = value_of(matches, "saver")?;
+    let market: Pubkey = value_of(matches, "market")?;
+    let saver_market = keypair_of(matches, "saver-market")?;
+    let open_orders = Keypair::new();
+    let payer = ctx.payer.pubkey();
+
+    let dex_market = ctx.get_dex_market(&market)?;
+    let lamports = ctx
+        .rpc
+        .get_minimum_balance_for_rent_exemption(OPEN_ORDERS_ACCOUNT_SIZE)?;
+
+    ctx.process(
+        &[
+            create_open_orders(&dex_market, &open_orders.pubkey(), &payer, lamports),
+            initialize_market(
+                &saver,
+                &saver_market.pubkey(),
+                &dex_market,
+                &open_orders.pubkey(),
+                &payer,
+            ),
+        ],
+        &[&saver_market, &open_orders],
+    )?;
+
+    println!("Saver market: {}", saver_market.pubkey());
+    Ok(())
+}
+
+fn command_set_market_limits(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver_market: Pubkey = value_of(matches, "saver-market")?;
+    let accounts = ctx.get_saver_market(&saver_market)?;
+
+    ctx.process(
+        &[accounts.set_limits(
+            value_of(matches, "max-notional")?,
+            value_of(matches, "max-swaps-per-slot")?,
+            value_of(matches, "max-daily-volume")?,
+        )],
+        &[],
+    )
+}
+
+fn command_set_market_oracle(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver_market: Pubkey = value_of(matches, "saver-market")?;
+    let oracle = match matches.value_of("oracle") {
+        Some(_) => value_of(matches, "oracle")?,
+        None => Pubkey::default(),
+    };
+    let accounts = ctx.get_saver_market(&saver_market)?;
+
+    ctx.process(
+        &[accounts.set_oracle(
+            &oracle,
+            value_of(matches, "band-bps")?,
+            value_of(matches, "max-age-slots")?,
+        )],
+        &[],
+    )
+}
+
+fn command_set_crank_tip(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver_market: Pubkey = value_of(matches, "saver-market")?;
+    let accounts = ctx.get_saver_market(&saver_market)?;
+
+    ctx.process(&[accounts.set_crank_tip(value_of(matches, "tip")?)], &[])
+}
+
+fn command_crank(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver_market: Pubkey = value_of(matches, "saver-market")?;
+    let accounts = ctx.get_saver_market(&saver_market)?;
+    let mut ixs = vec![];
+    let tip_account = match matches.value_of("tip-account") {
+        Some(_) => value_of(matches, "tip-account")?,
+        None => {
+            let payer = ctx.payer.pubkey();
+            let wallet = SwapWallet::associated(&payer, &accounts.dex_market);
+            let account = ctx
+                .rpc
+                .get_account_with_commitment(&wallet.pc_wallet, ctx.rpc.commitment())?;
+            if account.value.is_none() {
+                ixs.push(create_associated_token_account(
+                    &payer,
+                    &payer,
+                    &accounts.dex_market.pc_mint,
+                ));
+            }
+            wallet.pc_wallet
+        }
+    };
+    ixs.push(accounts.crank(&tip_account, value_of(matches, "limit")?));
+
+    ctx.process(&ixs, &[])
+}
+
+fn command_settle(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver_market: Pubkey = value_of(matches, "saver-market")?;
+    let accounts = ctx.get_saver_market(&saver_market)?;
+
+    ctx.process(&[accounts.settle()], &[])
+}
+
+fn command_withdraw_fees(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver_market: Pubkey = value_of(matches, "saver-market")?;
+    let accounts = ctx.get_saver_market(&saver_market)?;
+    let wallet = SwapWallet::associated(&ctx.payer.pubkey(), &accounts.dex_market);
+
+    ctx.process(
+        &[accounts.withdraw_fees(
+            &wallet.coin_wallet,
+            &wallet.pc_wallet,
+            value_of(matches, "coin-amount")?,
+            value_of(matches, "pc-amount")?,
+        )],
+        &[],
+    )
+}
+
+fn command_init_user_volume(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver_market: Pubkey = value_of(matches, "saver-market")?;
+    let accounts = ctx.get_saver_market(&saver_market)?;
+    let payer = ctx.payer.pubkey();
+
+    ctx.process(&[accounts.initialize_user_volume(&payer, &payer)], &[])
+}
+
+fn command_swap(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver_market: Pubkey = value_of(matches, "saver-market")?;
+    let params = swap_params_of(matches)?;
+
+    let accounts = ctx.get_saver_market(&saver_market)?;
+    let mut wallet = match matches.value_of("owner") {
+        Some(_) => {
+            let owner: Pubkey = value_of(matches, "owner")?;
+            SwapWallet::delegated(&owner, &ctx.payer.pubkey(), &accounts.dex_market)
+        }
+        None => SwapWallet::associated(&ctx.payer.pubkey(), &accounts.dex_market),
+    };
+    if matches.is_present("user-stats") {
+        wallet = wallet.with_user_stats(&accounts.saver);
+    }
+    if accounts.saver_state.allowlist_enabled {
+        wallet = wallet.with_allowlist_entry(&accounts.saver);
+    }
+    if accounts.saver_market_state.max_daily_volume_per_user > 0 {
+        wallet = wallet.with_user_volume(&accounts.saver_market);
+    }
+
+    ctx.process(&[accounts.swap(&wallet, &params)], &[])
+}
+
+fn command_show_saver(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver: Pubkey = value_of(matches, "saver")?;
+    let data = ctx.rpc.get_account_data(&saver)?;
+    println!("{}", load_saver_info(&ctx.rpc, saver, &data)?);
+    Ok(())
+}
+
+fn command_show_market(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver_market: Pubkey = value_of(matches, "saver-market")?;
+    let data = ctx.rpc.get_account_data(&saver_market)?;
+    println!("{}", load_saver_market_info(&ctx.rpc, saver_market, &data)?);
+    Ok(())
+}
+
+fn command_list_markets(ctx: &CliContext, matches: &ArgMatches<'_>) -> CliResult {
+    let saver: Pubkey = value_of(matches, "saver")?;
+
+    let accounts = ctx.rpc.get_program_accounts_with_config(
+        &serum_saver::ID,
+        RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::Memcmp(Memcmp {
+                    offset: 0,
+                    bytes: MemcmpEncodedBytes::Binary(
+                        bs58::encode(SaverMarket::discriminator()).into_string(),
+                    ),
+                    encoding: None,
+                }),
+                RpcFilterType::Memcmp(Memcmp {
+                    offset: 8,
+                    bytes: MemcmpEncodedBytes::Binary(saver.to_string()),
+                    encoding: None,
+                }),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        },
+    )?;
+
+    for (saver_market, account) in accounts {
+        let state = SaverMarket::try_deserialize(&mut account.data.as_slice())?;
+        println!("{} market: {}", saver_market, state.market);
+    }
+    Ok(())
+}
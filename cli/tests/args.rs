@@ -0,0 +1,143 @@
+use clap::ArgMatches;
+use serum_dex::{fees::FeeTier, instruction::SelfTradeBehavior, matching::Side};
+use serum_saver_cli::{app, fee_tier_of, swap_params_of, value_of};
+use solana_sdk::pubkey::Pubkey;
+
+const ADDRESS: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+
+fn subcommand_matches(args: &[&str]) -> ArgMatches<'static> {
+    let matches = app()
+        .get_matches_from_safe(std::iter::once("serum-saver").chain(args.iter().copied()))
+        .unwrap();
+    let (_name, matches) = matches.subcommand();
+    matches.unwrap().clone()
+}
+
+fn swap_args<'a>(extra: &[&'a str]) -> Vec<&'a str> {
+    let mut args = vec![
+        "swap",
+        "--saver-market",
+        ADDRESS,
+        "--side",
+        "bid",
+        "--limit-price",
+        "202",
+        "--max-coin-qty",
+        "1000",
+        "--max-pc-qty",
+        "2024444",
+    ];
+    args.extend_from_slice(extra);
+    args
+}
+
+#[test]
+fn swap_defaults() {
+    let matches = subcommand_matches(&swap_args(&[]));
+    let params = swap_params_of(&matches).unwrap();
+    assert!(matches!(params.side, Side::Bid));
+    assert_eq!(params.limit_price, 202);
+    assert_eq!(params.max_coin_qty, 1_000);
+    assert_eq!(params.max_native_pc_qty_including_fees, 2_024_444);
+    assert_eq!(params.limit, u16::MAX);
+    assert_eq!(params.max_price_impact_bps, 0);
+    assert!(matches!(
+        params.self_trade_behavior,
+        SelfTradeBehavior::AbortTransaction
+    ));
+    assert_eq!(params.client_order_id, 0);
+
+    let saver_market: Pubkey = value_of(&matches, "saver-market").unwrap();
+    assert_eq!(saver_market.to_string(), ADDRESS);
+}
+
+#[test]
+fn swap_options() {
+    let matches = subcommand_matches(&swap_args(&[
+        "--limit",
+        "5",
+        "--max-price-impact-bps",
+        "30",
+        "--client-order-id",
+        "42",
+    ]));
+    let params = swap_params_of(&matches).unwrap();
+    assert_eq!(params.limit, 5);
+    assert_eq!(params.max_price_impact_bps, 30);
+    assert_eq!(params.client_order_id, 42);
+
+    // `limit` is `u16` in `new_order`
+    let matches = subcommand_matches(&swap_args(&["--limit", "65536"]));
+    assert!(swap_params_of(&matches).is_err());
+}
+
+#[test]
+fn swap_self_trade_behavior() {
+    let behavior = |value| {
+        let matches = subcommand_matches(&swap_args(&["--self-trade-behavior", value]));
+        swap_params_of(&matches).unwrap().self_trade_behavior
+    };
+    assert!(matches!(
+        behavior("decrement-take"),
+        SelfTradeBehavior::DecrementTake
+    ));
+    assert!(matches!(
+        behavior("cancel-provide"),
+        SelfTradeBehavior::CancelProvide
+    ));
+    assert!(matches!(
+        behavior("abort-transaction"),
+        SelfTradeBehavior::AbortTransaction
+    ));
+
+    let args = swap_args(&["--self-trade-behavior", "ignore"]);
+    assert!(app()
+        .get_matches_from_safe(std::iter::once("serum-saver").chain(args))
+        .is_err());
+}
+
+#[test]
+fn swap_required_args() {
+    let args = ["serum-saver", "swap", "--saver-market", ADDRESS];
+    assert!(app().get_matches_from_safe(args.iter().copied()).is_err());
+}
+
+#[test]
+fn fee_tier() {
+    let matches = subcommand_matches(&["init-saver"]);
+    assert!(matches!(fee_tier_of(&matches).unwrap(), FeeTier::Base));
+
+    let matches = subcommand_matches(&[
+        "set-min-fee-tier",
+        "--saver",
+        ADDRESS,
+        "--min-fee-tier",
+        "srm3",
+    ]);
+    assert!(matches!(fee_tier_of(&matches).unwrap(), FeeTier::SRM3));
+
+    let matches = subcommand_matches(&[
+        "set-min-fee-tier",
+        "--saver",
+        ADDRESS,
+        "--min-fee-tier",
+        "msrm",
+    ]);
+    assert!(matches!(fee_tier_of(&matches).unwrap(), FeeTier::MSRM));
+}
+
+#[test]
+fn init_saver_msrm() {
+    assert!(!subcommand_matches(&["init-saver"]).is_present("msrm"));
+    assert!(subcommand_matches(&["init-saver", "--msrm"]).is_present("msrm"));
+}
+
+#[test]
+fn crank_tip_account() {
+    let args = ["crank", "--saver-market", ADDRESS];
+    assert!(subcommand_matches(&args).value_of("tip-account").is_none());
+
+    let matches = subcommand_matches(&[&args[..], &["--tip-account", ADDRESS]].concat());
+    let tip_account: Pubkey = value_of(&matches, "tip-account").unwrap();
+    assert_eq!(tip_account.to_string(), ADDRESS);
+}
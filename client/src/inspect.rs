@@ -0,0 +1,246 @@
+use anchor_lang::AccountDeserialize;
+use serum_dex::{
+    fees::FeeTier,
+    state::{AccountFlag, OpenOrders},
+};
+use serum_saver::{
+    dex::{get_fee_rates, FeeRates},
+    token::TokenAccount,
+    Saver, SaverMarket,
+};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use std::{convert::TryFrom, fmt, mem::size_of};
+
+use crate::to_pubkey;
+
+/// Decode `OpenOrders` from raw account data: `"serum" || data || "padding"`, only initialized
+/// OpenOrders is accepted.
+pub fn load_open_orders(data: &[u8]) -> Result<OpenOrders, ProgramError> {
+    if !data.starts_with(b"serum") {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let open_orders = data
+        .get(5..5 + size_of::<OpenOrders>())
+        .and_then(|data| bytemuck::try_from_bytes::<OpenOrders>(data).ok())
+        .copied()
+        .ok_or(ProgramError::InvalidAccountData)?;
+    if open_orders.account_flags != AccountFlag::Initialized as u64 | AccountFlag::OpenOrders as u64
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(open_orders)
+}
+
+fn load_token_account(data: &[u8]) -> Result<TokenAccount, ProgramError> {
+    TokenAccount::try_deserialize(&mut &data[..])
+}
+
+/// Rate as basis points with two decimals, from amount of one million units.
+struct Bps(u64);
+
+impl fmt::Display for Bps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:02} bps", self.0 / 100, self.0 % 100)
+    }
+}
+
+/// `fee_rates` are rates on regular (not stable) markets.
+#[derive(Debug, Clone, Copy)]
+pub struct SaverInfo {
+    pub address: Pubkey,
+    pub state: Saver,
+    pub srm_balance: u64,
+    pub fee_rates: FeeRates,
+}
+
+impl SaverInfo {
+    pub fn decode(
+        address: Pubkey,
+        saver_data: &[u8],
+        srm_vault_data: &[u8],
+    ) -> Result<Self, ProgramError> {
+        let state = Saver::try_deserialize(&mut &saver_data[..])?;
+        let srm_vault = load_token_account(srm_vault_data)?;
+        Ok(Self {
+            address,
+            state,
+            srm_balance: srm_vault.amount,
+            fee_rates: get_fee_rates(&Pubkey::default(), &srm_vault),
+        })
+    }
+}
+
+impl fmt::Display for SaverInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Saver: {}", self.address)?;
+        writeln!(f, "  authority: {}", self.state.authority)?;
+        writeln!(
+            f,
+            "  signer: {} (nonce {})",
+            self.state.signer, self.state.nonce
+        )?;
+        writeln!(f, "  dex program: {}", self.state.dex_program)?;
+        writeln!(f, "  allowlist enabled: {}", self.state.allowlist_enabled)?;
+        writeln!(f, "  srm vault: {}", self.state.srm_vault)?;
+        writeln!(f, "  srm balance: {}", self.srm_balance)?;
+        match FeeTier::try_from(self.state.min_fee_tier) {
+            Ok(fee_tier) => writeln!(f, "  min fee tier: {:?}", fee_tier)?,
+            Err(_) => writeln!(f, "  min fee tier: unknown ({})", self.state.min_fee_tier)?,
+        }
+        writeln!(f, "  fee tier: {:?}", self.fee_rates.fee_tier)?;
+        writeln!(
+            f,
+            "  taker fee: {}",
+            Bps(self.fee_rates.taker_fee(1_000_000))
+        )?;
+        write!(
+            f,
+            "  maker rebate: {}",
+            Bps(self.fee_rates.maker_rebate(1_000_000))
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OpenOrdersInfo {
+    pub address: Pubkey,
+    pub native_coin_free: u64,
+    pub native_coin_locked: u64,
+    pub native_pc_free: u64,
+    pub native_pc_locked: u64,
+    pub referrer_rebates_accrued: u64,
+}
+
+impl OpenOrdersInfo {
+    pub fn decode(address: Pubkey, data: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Self::from_open_orders(address, &load_open_orders(data)?))
+    }
+
+    /// Decode OpenOrders of `saver` market: account should be owned by saver DEX program
+    /// and OpenOrders should be owned by saver signer.
+    pub fn decode_saver_open_orders(
+        address: Pubkey,
+        account_owner: &Pubkey,
+        data: &[u8],
+        saver: &Saver,
+    ) -> Result<Self, ProgramError> {
+        if *account_owner != saver.dex_program {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let open_orders = load_open_orders(data)?;
+        if to_pubkey(open_orders.owner) != saver.signer {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self::from_open_orders(address, &open_orders))
+    }
+
+    fn from_open_orders(address: Pubkey, open_orders: &OpenOrders) -> Self {
+        let (native_coin_free, native_coin_total) =
+            (open_orders.native_coin_free, open_orders.native_coin_total);
+        let (native_pc_free, native_pc_total) =
+            (open_orders.native_pc_free, open_orders.native_pc_total);
+        Self {
+            address,
+            native_coin_free,
+            native_coin_locked: native_coin_total.saturating_sub(native_coin_free),
+            native_pc_free,
+            native_pc_locked: native_pc_total.saturating_sub(native_pc_free),
+            referrer_rebates_accrued: open_orders.referrer_rebates_accrued,
+        }
+    }
+}
+
+impl fmt::Display for OpenOrdersInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "OpenOrders: {}", self.address)?;
+        writeln!(
+            f,
+            "  coin free / locked: {} / {}",
+            self.native_coin_free, self.native_coin_locked
+        )?;
+        writeln!(
+            f,
+            "  pc free / locked: {} / {}",
+            self.native_pc_free, self.native_pc_locked
+        )?;
+        write!(f, "  referrer rebates: {}", self.referrer_rebates_accrued)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SaverMarketInfo {
+    pub address: Pubkey,
+    pub state: SaverMarket,
+    pub coin_vault_balance: u64,
+    pub pc_vault_balance: u64,
+    pub open_orders: OpenOrdersInfo,
+}
+
+impl SaverMarketInfo {
+    /// `open_orders_data` is data of `SaverMarket::open_orders`.
+    pub fn decode(
+        address: Pubkey,
+        saver_market_data: &[u8],
+        coin_vault_data: &[u8],
+        pc_vault_data: &[u8],
+        open_orders_data: &[u8],
+    ) -> Result<Self, ProgramError> {
+        let state = SaverMarket::try_deserialize(&mut &saver_market_data[..])?;
+        let open_orders = OpenOrdersInfo::decode(state.open_orders, open_orders_data)?;
+        Ok(Self {
+            address,
+            state,
+            coin_vault_balance: load_token_account(coin_vault_data)?.amount,
+            pc_vault_balance: load_token_account(pc_vault_data)?.amount,
+            open_orders,
+        })
+    }
+}
+
+impl fmt::Display for SaverMarketInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "SaverMarket: {}", self.address)?;
+        writeln!(f, "  saver: {}", self.state.saver)?;
+        writeln!(f, "  market: {}", self.state.market)?;
+        writeln!(f, "  coin lot size: {}", self.state.coin_lot_size)?;
+        writeln!(f, "  pc lot size: {}", self.state.pc_lot_size)?;
+        writeln!(
+            f,
+            "  decimals coin / pc: {} / {}",
+            self.state.coin_decimals, self.state.pc_decimals
+        )?;
+        writeln!(
+            f,
+            "  coin vault: {} (balance {})",
+            self.state.coin_vault, self.coin_vault_balance
+        )?;
+        writeln!(
+            f,
+            "  pc vault: {} (balance {})",
+            self.state.pc_vault, self.pc_vault_balance
+        )?;
+        writeln!(
+            f,
+            "  max notional per swap: {}",
+            self.state.max_notional_per_swap
+        )?;
+        writeln!(f, "  max swaps per slot: {}", self.state.max_swaps_per_slot)?;
+        writeln!(
+            f,
+            "  oracle: {} (band {} bps, max age {} slots)",
+            self.state.oracle, self.state.oracle_band_bps, self.state.max_oracle_age_slots
+        )?;
+        writeln!(
+            f,
+            "  max daily volume per user: {}",
+            self.state.max_daily_volume_per_user
+        )?;
+        writeln!(
+            f,
+            "  fees coin / pc: {} / {}",
+            self.state.coin_fees, self.state.pc_fees
+        )?;
+        writeln!(f, "  crank tip: {}", self.state.crank_tip)?;
+        write!(f, "{}", self.open_orders)
+    }
+}
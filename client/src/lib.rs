@@ -0,0 +1,629 @@
+use anchor_lang::{InstructionData, ToAccountMetas};
+use serum_dex::{
+    fees::FeeTier,
+    instruction::SelfTradeBehavior,
+    matching::Side,
+    state::{gen_vault_signer_key, MarketState, OpenOrders},
+};
+use serum_saver::{Saver, SaverMarket};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction, system_program, sysvar,
+};
+use spl_associated_token_account::{
+    create_associated_token_account, get_associated_token_address as gata,
+};
+use std::mem::size_of;
+
+pub use serum_saver;
+
+pub mod inspect;
+pub mod quote;
+
+/// Size of OpenOrders account: `"serum" || data || "padding"`.
+pub const OPEN_ORDERS_ACCOUNT_SIZE: usize = size_of::<OpenOrders>() + 12;
+
+pub fn get_saver_signer(saver: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[saver.as_ref()], &serum_saver::ID)
+}
+
+pub fn get_saver_market_vault(saver_market: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[saver_market.as_ref(), mint.as_ref()], &serum_saver::ID)
+}
+
+/// `wallet` is owner of swapped token accounts, not a delegate signing swaps.
+pub fn get_user_stats(saver: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"stats", saver.as_ref(), wallet.as_ref()],
+        &serum_saver::ID,
+    )
+}
+
+pub fn get_user_volume(saver_market: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"volume", saver_market.as_ref(), wallet.as_ref()],
+        &serum_saver::ID,
+    )
+}
+
+pub fn get_allowlist_entry(saver: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"allowlist", saver.as_ref(), wallet.as_ref()],
+        &serum_saver::ID,
+    )
+}
+
+/// Create associated SRM or MSRM account of saver signer, should be called before
+/// `initialize_saver`. `srm_mint` should be `srm_token::ID` or `msrm_token::ID`.
+pub fn create_srm_vault(saver: &Pubkey, srm_mint: &Pubkey, payer: &Pubkey) -> Instruction {
+    let (signer, _nonce) = get_saver_signer(saver);
+    create_associated_token_account(payer, &signer, srm_mint)
+}
+
+/// `saver` should be a new account and sign transaction, `srm_mint` is mint of vault created by
+/// `create_srm_vault`. Only markets of `dex_program` can be added to the saver.
+pub fn initialize_saver(
+    saver: &Pubkey,
+    srm_mint: &Pubkey,
+    dex_program: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    min_fee_tier: FeeTier,
+) -> Instruction {
+    let (signer, nonce) = get_saver_signer(saver);
+    Instruction::new_with_bytes(
+        serum_saver::ID,
+        &serum_saver::instruction::InitializeSaver {
+            nonce,
+            min_fee_tier: min_fee_tier.into(),
+        }
+        .data(),
+        serum_saver::accounts::InitializeSaver {
+            saver: *saver,
+            signer,
+
+            srm_vault: gata(&signer, srm_mint),
+
+            dex_program: *dex_program,
+
+            authority: *authority,
+
+            payer: *payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `authority` should sign transaction.
+pub fn set_min_fee_tier(saver: &Pubkey, authority: &Pubkey, min_fee_tier: FeeTier) -> Instruction {
+    Instruction::new_with_bytes(
+        serum_saver::ID,
+        &serum_saver::instruction::SetMinFeeTier {
+            min_fee_tier: min_fee_tier.into(),
+        }
+        .data(),
+        serum_saver::accounts::SetMinFeeTier {
+            saver: *saver,
+            authority: *authority,
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `authority` should sign transaction.
+pub fn set_allowlist_enabled(saver: &Pubkey, authority: &Pubkey, enabled: bool) -> Instruction {
+    Instruction::new_with_bytes(
+        serum_saver::ID,
+        &serum_saver::instruction::SetAllowlistEnabled { enabled }.data(),
+        serum_saver::accounts::SetAllowlistEnabled {
+            saver: *saver,
+            authority: *authority,
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `authority` should sign transaction.
+pub fn add_to_allowlist(
+    saver: &Pubkey,
+    authority: &Pubkey,
+    wallet: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (allowlist_entry, nonce) = get_allowlist_entry(saver, wallet);
+    Instruction::new_with_bytes(
+        serum_saver::ID,
+        &serum_saver::instruction::AddToAllowlist { nonce }.data(),
+        serum_saver::accounts::AddToAllowlist {
+            allowlist_entry,
+
+            saver: *saver,
+            authority: *authority,
+            wallet: *wallet,
+
+            payer: *payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// `authority` should sign transaction, rent of the entry is returned to `authority`.
+pub fn remove_from_allowlist(saver: &Pubkey, authority: &Pubkey, wallet: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        serum_saver::ID,
+        &serum_saver::instruction::RemoveFromAllowlist {}.data(),
+        serum_saver::accounts::RemoveFromAllowlist {
+            allowlist_entry: get_allowlist_entry(saver, wallet).0,
+
+            saver: *saver,
+            authority: *authority,
+        }
+        .to_account_metas(None),
+    )
+}
+
+pub fn initialize_user_stats(saver: &Pubkey, wallet: &Pubkey, payer: &Pubkey) -> Instruction {
+    let (user_stats, nonce) = get_user_stats(saver, wallet);
+    Instruction::new_with_bytes(
+        serum_saver::ID,
+        &serum_saver::instruction::InitializeUserStats { nonce }.data(),
+        serum_saver::accounts::InitializeUserStats {
+            user_stats,
+
+            saver: *saver,
+            wallet: *wallet,
+
+            payer: *payer,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// Allocate OpenOrders account owned by DEX, should be called before `initialize_market`.
+/// `open_orders` should sign transaction.
+pub fn create_open_orders(
+    dex_market: &DexMarket,
+    open_orders: &Pubkey,
+    payer: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    system_instruction::create_account(
+        payer,
+        open_orders,
+        lamports,
+        OPEN_ORDERS_ACCOUNT_SIZE as u64,
+        &dex_market.program_id,
+    )
+}
+
+/// `saver_market` should be a new account and sign transaction.
+pub fn initialize_market(
+    saver: &Pubkey,
+    saver_market: &Pubkey,
+    dex_market: &DexMarket,
+    dex_open_orders: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (signer, _nonce) = get_saver_signer(saver);
+    let (coin_vault, coin_vault_nonce) =
+        get_saver_market_vault(saver_market, &dex_market.coin_mint);
+    let (pc_vault, pc_vault_nonce) = get_saver_market_vault(saver_market, &dex_market.pc_mint);
+    Instruction::new_with_bytes(
+        serum_saver::ID,
+        &serum_saver::instruction::InitializeMarket {
+            coin_vault_nonce,
+            pc_vault_nonce,
+        }
+        .data(),
+        serum_saver::accounts::InitializeMarket {
+            saver_market: *saver_market,
+
+            saver: *saver,
+            signer,
+
+            coin_mint: dex_market.coin_mint,
+            coin_vault,
+            pc_mint: dex_market.pc_mint,
+            pc_vault,
+
+            dex_program: dex_market.program_id,
+            dex_market: dex_market.market,
+            dex_open_orders: *dex_open_orders,
+
+            payer: *payer,
+            system_program: system_program::ID,
+            spl_token_program: serum_saver::token::ID,
+            rent: sysvar::rent::ID,
+        }
+        .to_account_metas(None),
+    )
+}
+
+/// Serum market accounts decoded from `MarketState`.
+#[derive(Debug, Clone, Copy)]
+pub struct DexMarket {
+    pub program_id: Pubkey,
+    pub market: Pubkey,
+    pub request_queue: Pubkey,
+    pub event_queue: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub coin_mint: Pubkey,
+    pub coin_vault: Pubkey,
+    pub pc_mint: Pubkey,
+    pub pc_vault: Pubkey,
+    pub vault_signer: Pubkey,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+}
+
+impl DexMarket {
+    pub fn new(program_id: Pubkey, market: &MarketState) -> Result<Self, ProgramError> {
+        let own_address = to_pubkey(market.own_address);
+        Ok(Self {
+            program_id,
+            market: own_address,
+            request_queue: to_pubkey(market.req_q),
+            event_queue: to_pubkey(market.event_q),
+            bids: to_pubkey(market.bids),
+            asks: to_pubkey(market.asks),
+            coin_mint: to_pubkey(market.coin_mint),
+            coin_vault: to_pubkey(market.coin_vault),
+            pc_mint: to_pubkey(market.pc_mint),
+            pc_vault: to_pubkey(market.pc_vault),
+            vault_signer: gen_vault_signer_key(
+                market.vault_signer_nonce,
+                &own_address,
+                &program_id,
+            )?,
+            coin_lot_size: market.coin_lot_size,
+            pc_lot_size: market.pc_lot_size,
+        })
+    }
+}
+
+/// Decode `MarketState` from raw account data: `"serum" || data || "padding"`.
+pub fn load_market_state(data: &[u8]) -> Result<MarketState, ProgramError> {
+    data.get(5..5 + size_of::<MarketState>())
+        .and_then(|data| bytemuck::try_from_bytes::<MarketState>(data).ok())
+        .copied()
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+fn to_pubkey(key: [u64; 4]) -> Pubkey {
+    Pubkey::new(bytemuck::cast_slice(&key[..]))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SwapParams {
+    pub side: Side,
+    pub limit_price: u64,
+    pub max_coin_qty: u64,
+    pub max_native_pc_qty_including_fees: u64,
+    /// Max number of matched orders.
+    pub limit: u16,
+    /// Max distance of matched orders from the best price, zero means no cap.
+    pub max_price_impact_bps: u64,
+    pub self_trade_behavior: SelfTradeBehavior,
+    pub client_order_id: u64,
+}
+
+/// User token accounts used in swap.
+#[derive(Debug, Clone, Copy)]
+pub struct SwapWallet {
+    pub owner: Pubkey,
+    pub coin_wallet: Pubkey,
+    pub pc_wallet: Pubkey,
+    pub wallet_signer: Pubkey,
+    pub user_stats: Option<Pubkey>,
+    pub allowlist_entry: Option<Pubkey>,
+    pub user_volume: Option<Pubkey>,
+}
+
+impl SwapWallet {
+    /// Associated token accounts of `owner`, without `UserStats`.
+    pub fn associated(owner: &Pubkey, dex_market: &DexMarket) -> Self {
+        Self {
+            owner: *owner,
+            coin_wallet: gata(owner, &dex_market.coin_mint),
+            pc_wallet: gata(owner, &dex_market.pc_mint),
+            wallet_signer: *owner,
+            user_stats: None,
+            allowlist_entry: None,
+            user_volume: None,
+        }
+    }
+
+    /// Associated token accounts of `owner`, swap signed by `wallet_signer`. Paying wallet should
+    /// approve `wallet_signer` as delegate, see `approve_swap`.
+    pub fn delegated(owner: &Pubkey, wallet_signer: &Pubkey, dex_market: &DexMarket) -> Self {
+        Self {
+            wallet_signer: *wallet_signer,
+            ..Self::associated(owner, dex_market)
+        }
+    }
+
+    pub fn with_user_stats(self, saver: &Pubkey) -> Self {
+        Self {
+            user_stats: Some(get_user_stats(saver, &self.owner).0),
+            ..self
+        }
+    }
+
+    /// Required if saver market limits daily volume.
+    pub fn with_user_volume(self, saver_market: &Pubkey) -> Self {
+        Self {
+            user_volume: Some(get_user_volume(saver_market, &self.owner).0),
+            ..self
+        }
+    }
+
+    /// Required if allowlist is enabled on saver.
+    pub fn with_allowlist_entry(self, saver: &Pubkey) -> Self {
+        Self {
+            allowlist_entry: Some(get_allowlist_entry(saver, &self.owner).0),
+            ..self
+        }
+    }
+}
+
+/// Allow swaps signed by `wallet_signer` (e.g. a bot key) to spend up to `amount` from `wallet`.
+/// Token account has one delegate, so this replaces previous approval of `wallet`.
+/// Use `spl_token::instruction::revoke` to disallow.
+pub fn approve_swap(
+    wallet: &Pubkey,
+    owner: &Pubkey,
+    wallet_signer: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    spl_token::instruction::approve(&spl_token::ID, wallet, wallet_signer, owner, &[], amount)
+}
+
+/// `Saver` and `SaverMarket` with Serum market, enough for building market instructions.
+#[derive(Debug, Clone, Copy)]
+pub struct SaverMarketAccounts {
+    pub saver: Pubkey,
+    pub saver_state: Saver,
+    pub saver_market: Pubkey,
+    pub saver_market_state: SaverMarket,
+    pub dex_market: DexMarket,
+}
+
+impl SaverMarketAccounts {
+    /// Limits are in native pc quantity without fees, zero means no limit. Saver `authority`
+    /// should sign transaction.
+    pub fn set_limits(
+        &self,
+        max_notional_per_swap: u64,
+        max_swaps_per_slot: u64,
+        max_daily_volume_per_user: u64,
+    ) -> Instruction {
+        Instruction::new_with_bytes(
+            serum_saver::ID,
+            &serum_saver::instruction::SetMarketLimits {
+                max_notional_per_swap,
+                max_swaps_per_slot,
+                max_daily_volume_per_user,
+            }
+            .data(),
+            serum_saver::accounts::SetMarketLimits {
+                saver_market: self.saver_market,
+
+                saver: self.saver,
+                authority: self.saver_state.authority,
+            }
+            .to_account_metas(None),
+        )
+    }
+
+    /// `Pubkey::default()` disables oracle check. Saver `authority` should sign transaction.
+    pub fn set_oracle(
+        &self,
+        oracle: &Pubkey,
+        oracle_band_bps: u64,
+        max_oracle_age_slots: u64,
+    ) -> Instruction {
+        Instruction::new_with_bytes(
+            serum_saver::ID,
+            &serum_saver::instruction::SetMarketOracle {
+                oracle: *oracle,
+                oracle_band_bps,
+                max_oracle_age_slots,
+            }
+            .data(),
+            serum_saver::accounts::SetMarketOracle {
+                saver_market: self.saver_market,
+
+                saver: self.saver,
+                authority: self.saver_state.authority,
+            }
+            .to_account_metas(None),
+        )
+    }
+
+    /// Saver `authority` should sign transaction.
+    pub fn set_crank_tip(&self, crank_tip: u64) -> Instruction {
+        Instruction::new_with_bytes(
+            serum_saver::ID,
+            &serum_saver::instruction::SetCrankTip { crank_tip }.data(),
+            serum_saver::accounts::SetCrankTip {
+                saver_market: self.saver_market,
+
+                saver: self.saver,
+                authority: self.saver_state.authority,
+            }
+            .to_account_metas(None),
+        )
+    }
+
+    /// Consume events of saver OpenOrders, tip is paid to `tip_wallet` (pc token account).
+    pub fn crank(&self, tip_wallet: &Pubkey, limit: u16) -> Instruction {
+        Instruction::new_with_bytes(
+            serum_saver::ID,
+            &serum_saver::instruction::Crank { limit }.data(),
+            serum_saver::accounts::Crank {
+                saver: self.saver,
+                signer: self.saver_state.signer,
+
+                saver_market: self.saver_market,
+
+                pc_vault: self.saver_market_state.pc_vault,
+                tip_wallet: *tip_wallet,
+
+                market: self.dex_market.market,
+                open_orders: self.saver_market_state.open_orders,
+                event_queue: self.dex_market.event_queue,
+
+                dex_program: self.dex_market.program_id,
+                spl_token_program: serum_saver::token::ID,
+            }
+            .to_account_metas(None),
+        )
+    }
+
+    /// Settle free funds of saver OpenOrders to saver vaults as market fees.
+    pub fn settle(&self) -> Instruction {
+        Instruction::new_with_bytes(
+            serum_saver::ID,
+            &serum_saver::instruction::Settle {}.data(),
+            serum_saver::accounts::Settle {
+                saver: self.saver,
+                signer: self.saver_state.signer,
+
+                saver_market: self.saver_market,
+
+                coin_vault: self.saver_market_state.coin_vault,
+                pc_vault: self.saver_market_state.pc_vault,
+
+                market: self.dex_market.market,
+                open_orders: self.saver_market_state.open_orders,
+                dex_coin_vault: self.dex_market.coin_vault,
+                dex_pc_vault: self.dex_market.pc_vault,
+                dex_vault_signer: self.dex_market.vault_signer,
+
+                dex_program: self.dex_market.program_id,
+                spl_token_program: serum_saver::token::ID,
+            }
+            .to_account_metas(None),
+        )
+    }
+
+    /// Withdraw collected market fees. Saver `authority` should sign transaction.
+    pub fn withdraw_fees(
+        &self,
+        coin_wallet: &Pubkey,
+        pc_wallet: &Pubkey,
+        coin_amount: u64,
+        pc_amount: u64,
+    ) -> Instruction {
+        Instruction::new_with_bytes(
+            serum_saver::ID,
+            &serum_saver::instruction::WithdrawFees {
+                coin_amount,
+                pc_amount,
+            }
+            .data(),
+            serum_saver::accounts::WithdrawFees {
+                saver: self.saver,
+                signer: self.saver_state.signer,
+                authority: self.saver_state.authority,
+
+                saver_market: self.saver_market,
+
+                coin_vault: self.saver_market_state.coin_vault,
+                pc_vault: self.saver_market_state.pc_vault,
+
+                coin_wallet: *coin_wallet,
+                pc_wallet: *pc_wallet,
+
+                spl_token_program: serum_saver::token::ID,
+            }
+            .to_account_metas(None),
+        )
+    }
+
+    pub fn initialize_user_volume(&self, wallet: &Pubkey, payer: &Pubkey) -> Instruction {
+        let (user_volume, nonce) = get_user_volume(&self.saver_market, wallet);
+        Instruction::new_with_bytes(
+            serum_saver::ID,
+            &serum_saver::instruction::InitializeUserVolume { nonce }.data(),
+            serum_saver::accounts::InitializeUserVolume {
+                user_volume,
+
+                saver_market: self.saver_market,
+                wallet: *wallet,
+
+                payer: *payer,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+        )
+    }
+
+    pub fn swap(&self, wallet: &SwapWallet, params: &SwapParams) -> Instruction {
+        let mut accounts = serum_saver::accounts::Swap {
+            saver: self.saver,
+            signer: self.saver_state.signer,
+            srm_vault: self.saver_state.srm_vault,
+
+            saver_market: self.saver_market,
+
+            coin_vault: self.saver_market_state.coin_vault,
+            pc_vault: self.saver_market_state.pc_vault,
+
+            coin_wallet: wallet.coin_wallet,
+            pc_wallet: wallet.pc_wallet,
+            wallet_signer: wallet.wallet_signer,
+
+            market: self.dex_market.market,
+            open_orders: self.saver_market_state.open_orders,
+            request_queue: self.dex_market.request_queue,
+            event_queue: self.dex_market.event_queue,
+            bids: self.dex_market.bids,
+            asks: self.dex_market.asks,
+            dex_coin_vault: self.dex_market.coin_vault,
+            dex_pc_vault: self.dex_market.pc_vault,
+            dex_vault_signer: self.dex_market.vault_signer,
+
+            dex_program: self.dex_market.program_id,
+            spl_token_program: serum_saver::token::ID,
+        }
+        .to_account_metas(None);
+        if let Some(user_stats) = wallet.user_stats {
+            accounts.push(AccountMeta::new(user_stats, false));
+        }
+        if let Some(allowlist_entry) = wallet.allowlist_entry {
+            accounts.push(AccountMeta::new_readonly(allowlist_entry, false));
+        }
+        if let Some(user_volume) = wallet.user_volume {
+            accounts.push(AccountMeta::new(user_volume, false));
+        }
+        if self.saver_market_state.oracle != Pubkey::default() {
+            accounts.push(AccountMeta::new_readonly(
+                self.saver_market_state.oracle,
+                false,
+            ));
+        }
+
+        Instruction::new_with_bytes(
+            serum_saver::ID,
+            &serum_saver::instruction::Swap {
+                side: params.side.into(),
+                limit_price: params.limit_price,
+                max_coin_qty: params.max_coin_qty,
+                max_native_pc_qty_including_fees: params.max_native_pc_qty_including_fees,
+                limit: params.limit,
+                max_price_impact_bps: params.max_price_impact_bps,
+                self_trade_behavior: params.self_trade_behavior.into(),
+                client_order_id: params.client_order_id,
+            }
+            .data(),
+            accounts,
+        )
+    }
+}
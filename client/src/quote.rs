@@ -0,0 +1,119 @@
+use serum_dex::{fees::FeeTier, matching::Side, state::Market};
+use serum_saver::dex::{
+    clamp_limit_price, get_taker_fee_saved, iter_orders, simulate_fill, FeeRates,
+};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::SwapParams;
+
+pub use serum_saver::dex::Order;
+
+/// Expected result of `Swap`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quote {
+    /// Native coin quantity received on `Side::Bid` or spent on `Side::Ask`.
+    pub native_coin_qty: u64,
+    /// Native pc quantity spent on `Side::Bid` or received on `Side::Ask`, including fees.
+    pub native_pc_qty: u64,
+    /// Average execution price in pc lots per coin lot, same units as `limit_price`.
+    pub average_price: f64,
+    pub fee: u64,
+    /// Fee which would be paid on `FeeTier::Base` minus `fee`.
+    pub fee_saved: u64,
+}
+
+/// Snapshot of Serum orderbook, bids sorted from best to worst price, same for asks.
+#[derive(Debug, Clone)]
+pub struct Orderbook {
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub bids: Vec<Order>,
+    pub asks: Vec<Order>,
+}
+
+impl Orderbook {
+    /// Load orderbook from raw data of market, bids and asks accounts.
+    pub fn load(
+        program_id: &Pubkey,
+        market: &Pubkey,
+        market_data: &mut [u8],
+        bids_data: &mut [u8],
+        asks_data: &mut [u8],
+    ) -> Result<Self, ProgramError> {
+        let (mut market_lamports, mut bids_lamports, mut asks_lamports) = (0, 0, 0);
+        let market_info = account_info(market, program_id, &mut market_lamports, market_data);
+        let market = Market::load(&market_info, program_id)?;
+
+        let bids_key = crate::to_pubkey(market.bids);
+        let bids_info = account_info(&bids_key, program_id, &mut bids_lamports, bids_data);
+        let bids = iter_orders(&market.load_bids_mut(&bids_info)?, Side::Bid).collect();
+
+        let asks_key = crate::to_pubkey(market.asks);
+        let asks_info = account_info(&asks_key, program_id, &mut asks_lamports, asks_data);
+        let asks = iter_orders(&market.load_asks_mut(&asks_info)?, Side::Ask).collect();
+
+        Ok(Self {
+            coin_lot_size: market.coin_lot_size,
+            pc_lot_size: market.pc_lot_size,
+            bids,
+            asks,
+        })
+    }
+
+    /// Simulate immediate-or-cancel order of the saver with `fee_tier`, same as `Swap` do.
+    pub fn quote(&self, fee_tier: FeeTier, params: &SwapParams) -> Quote {
+        let (orders, max_pc_qty) = match params.side {
+            Side::Bid => {
+                let max_native_pc_qty = FeeRates::new(fee_tier)
+                    .remove_taker_fee(params.max_native_pc_qty_including_fees);
+                (&self.asks, max_native_pc_qty / self.pc_lot_size)
+            }
+            Side::Ask => (&self.bids, u64::MAX),
+        };
+        let limit_price = match orders.first() {
+            Some(order) => clamp_limit_price(
+                params.side,
+                params.limit_price,
+                order.price,
+                params.max_price_impact_bps,
+            ),
+            None => params.limit_price,
+        };
+        let fill = simulate_fill(
+            orders,
+            params.side,
+            limit_price,
+            params.max_coin_qty,
+            max_pc_qty,
+            params.limit,
+        );
+
+        let (coin_qty, pc_qty) = (fill.coin_qty, fill.pc_qty);
+        let native_pc_qty = pc_qty * self.pc_lot_size;
+        let fee = fee_tier.taker_fee(native_pc_qty);
+        let fee_saved = get_taker_fee_saved(fee_tier, native_pc_qty);
+        Quote {
+            native_coin_qty: coin_qty * self.coin_lot_size,
+            native_pc_qty: match params.side {
+                Side::Bid => native_pc_qty + fee,
+                Side::Ask => native_pc_qty - fee,
+            },
+            average_price: if coin_qty == 0 {
+                0.0
+            } else {
+                pc_qty as f64 / coin_qty as f64
+            },
+            fee,
+            fee_saved,
+        }
+    }
+}
+
+fn account_info<'a>(
+    key: &'a Pubkey,
+    owner: &'a Pubkey,
+    lamports: &'a mut u64,
+    data: &'a mut [u8],
+) -> AccountInfo<'a> {
+    AccountInfo::new(key, false, true, lamports, data, owner, false, 0)
+}
@@ -0,0 +1,155 @@
+use anchor_lang::AccountSerialize;
+use serum_dex::state::{AccountFlag, OpenOrders};
+use serum_saver_client::{
+    inspect::{OpenOrdersInfo, SaverMarketInfo},
+    serum_saver::{Saver, SaverMarket},
+};
+use solana_program::{program_error::ProgramError, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::{Account as TokenAccount, AccountState};
+
+fn open_orders_data(owner: &Pubkey, update: impl FnOnce(&mut OpenOrders)) -> Vec<u8> {
+    let mut open_orders: OpenOrders = bytemuck::Zeroable::zeroed();
+    open_orders.account_flags = AccountFlag::Initialized as u64 | AccountFlag::OpenOrders as u64;
+    open_orders.owner = bytemuck::cast(owner.to_bytes());
+    update(&mut open_orders);
+
+    let mut data = b"serum".to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(&open_orders));
+    data.extend_from_slice(b"padding");
+    data
+}
+
+fn token_account_data(amount: u64) -> Vec<u8> {
+    let mut data = vec![0; TokenAccount::LEN];
+    TokenAccount {
+        amount,
+        state: AccountState::Initialized,
+        ..TokenAccount::default()
+    }
+    .pack_into_slice(&mut data);
+    data
+}
+
+fn anchor_data<T: AccountSerialize>(state: &T) -> Vec<u8> {
+    let mut data = vec![];
+    state.try_serialize(&mut data).unwrap();
+    data
+}
+
+#[test]
+fn decode_open_orders() {
+    let address = Pubkey::new_unique();
+    let data = open_orders_data(&Pubkey::new_unique(), |open_orders| {
+        open_orders.native_coin_free = 10;
+        open_orders.native_coin_total = 25;
+        open_orders.native_pc_free = 7;
+        open_orders.native_pc_total = 7;
+        open_orders.referrer_rebates_accrued = 3;
+    });
+
+    let info = OpenOrdersInfo::decode(address, &data).unwrap();
+    assert_eq!(info.address, address);
+    assert_eq!((info.native_coin_free, info.native_coin_locked), (10, 15));
+    assert_eq!((info.native_pc_free, info.native_pc_locked), (7, 0));
+    assert_eq!(info.referrer_rebates_accrued, 3);
+}
+
+#[test]
+fn decode_malformed_open_orders() {
+    let address = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+
+    // Free above total is not possible in serum, but should not panic
+    let data = open_orders_data(&owner, |open_orders| {
+        open_orders.native_coin_free = 10;
+        open_orders.native_pc_free = 10;
+    });
+    let info = OpenOrdersInfo::decode(address, &data).unwrap();
+    assert_eq!((info.native_coin_locked, info.native_pc_locked), (0, 0));
+
+    let mut data = open_orders_data(&owner, |_| {});
+    data[..5].copy_from_slice(b"saver");
+    assert_eq!(
+        OpenOrdersInfo::decode(address, &data).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+
+    let data = open_orders_data(&owner, |open_orders| {
+        open_orders.account_flags = AccountFlag::Initialized as u64 | AccountFlag::Market as u64;
+    });
+    assert_eq!(
+        OpenOrdersInfo::decode(address, &data).unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+
+    assert_eq!(
+        OpenOrdersInfo::decode(address, b"serum").unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+}
+
+#[test]
+fn decode_saver_open_orders() {
+    let address = Pubkey::new_unique();
+    let saver = Saver {
+        signer: Pubkey::new_unique(),
+        dex_program: Pubkey::new_unique(),
+        ..Saver::default()
+    };
+    let data = open_orders_data(&saver.signer, |_| {});
+
+    assert!(
+        OpenOrdersInfo::decode_saver_open_orders(address, &saver.dex_program, &data, &saver)
+            .is_ok()
+    );
+    assert_eq!(
+        OpenOrdersInfo::decode_saver_open_orders(address, &Pubkey::new_unique(), &data, &saver)
+            .unwrap_err(),
+        ProgramError::IncorrectProgramId
+    );
+
+    let data = open_orders_data(&Pubkey::new_unique(), |_| {});
+    assert_eq!(
+        OpenOrdersInfo::decode_saver_open_orders(address, &saver.dex_program, &data, &saver)
+            .unwrap_err(),
+        ProgramError::InvalidAccountData
+    );
+}
+
+#[test]
+fn decode_saver_market() {
+    let address = Pubkey::new_unique();
+    let state = SaverMarket {
+        open_orders: Pubkey::new_unique(),
+        coin_fees: 5,
+        ..SaverMarket::default()
+    };
+    let open_orders = open_orders_data(&Pubkey::new_unique(), |open_orders| {
+        open_orders.native_coin_free = 1;
+        open_orders.native_pc_free = 2;
+    });
+
+    let info = SaverMarketInfo::decode(
+        address,
+        &anchor_data(&state),
+        &token_account_data(100),
+        &token_account_data(200),
+        &open_orders,
+    )
+    .unwrap();
+    assert_eq!(info.address, address);
+    assert_eq!(info.state.coin_fees, 5);
+    assert_eq!((info.coin_vault_balance, info.pc_vault_balance), (100, 200));
+    assert_eq!(info.open_orders.address, state.open_orders);
+    assert_eq!(info.open_orders.native_coin_free, 1);
+    assert_eq!(info.open_orders.native_pc_free, 2);
+
+    assert!(SaverMarketInfo::decode(
+        address,
+        &anchor_data(&Saver::default()),
+        &token_account_data(100),
+        &token_account_data(200),
+        &open_orders,
+    )
+    .is_err());
+}
@@ -0,0 +1,355 @@
+use anchor_lang::AnchorDeserialize;
+use serum_dex::{
+    instruction::{msrm_token, SelfTradeBehavior},
+    matching::Side,
+};
+use serum_saver_client::{
+    add_to_allowlist, create_srm_vault, get_allowlist_entry, get_saver_signer, get_user_stats,
+    get_user_volume, initialize_saver, remove_from_allowlist,
+    serum_saver::{self, instruction, Saver, SaverMarket},
+    DexMarket, SaverMarketAccounts, SwapParams, SwapWallet,
+};
+use solana_program::{hash::hash, instruction::Instruction, pubkey::Pubkey, system_program};
+use spl_associated_token_account::get_associated_token_address;
+
+/// `(pubkey, is_signer, is_writable)` of every account.
+fn keys(ix: &Instruction) -> Vec<(Pubkey, bool, bool)> {
+    ix.accounts
+        .iter()
+        .map(|meta| (meta.pubkey, meta.is_signer, meta.is_writable))
+        .collect()
+}
+
+/// Arguments of anchor instruction `name`, after checking its sighash.
+fn args<T: AnchorDeserialize>(ix: &Instruction, name: &str) -> T {
+    assert_eq!(ix.program_id, serum_saver::ID);
+    let sighash = hash(format!("global:{}", name).as_bytes());
+    assert_eq!(ix.data[..8], sighash.to_bytes()[..8]);
+    T::try_from_slice(&ix.data[8..]).unwrap()
+}
+
+fn accounts() -> SaverMarketAccounts {
+    SaverMarketAccounts {
+        saver: Pubkey::new_unique(),
+        saver_state: Saver {
+            signer: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            srm_vault: Pubkey::new_unique(),
+            dex_program: Pubkey::new_unique(),
+            ..Saver::default()
+        },
+        saver_market: Pubkey::new_unique(),
+        saver_market_state: SaverMarket {
+            open_orders: Pubkey::new_unique(),
+            coin_vault: Pubkey::new_unique(),
+            pc_vault: Pubkey::new_unique(),
+            ..SaverMarket::default()
+        },
+        dex_market: DexMarket {
+            program_id: Pubkey::new_unique(),
+            market: Pubkey::new_unique(),
+            request_queue: Pubkey::new_unique(),
+            event_queue: Pubkey::new_unique(),
+            bids: Pubkey::new_unique(),
+            asks: Pubkey::new_unique(),
+            coin_mint: Pubkey::new_unique(),
+            coin_vault: Pubkey::new_unique(),
+            pc_mint: Pubkey::new_unique(),
+            pc_vault: Pubkey::new_unique(),
+            vault_signer: Pubkey::new_unique(),
+            coin_lot_size: 1,
+            pc_lot_size: 1,
+        },
+    }
+}
+
+fn swap_params() -> SwapParams {
+    SwapParams {
+        side: Side::Ask,
+        limit_price: 198,
+        max_coin_qty: 10,
+        max_native_pc_qty_including_fees: 2_000,
+        limit: 5,
+        max_price_impact_bps: 30,
+        self_trade_behavior: SelfTradeBehavior::CancelProvide,
+        client_order_id: 7,
+    }
+}
+
+#[test]
+fn swap() {
+    let accounts = accounts();
+    let (saver, market) = (&accounts.saver_state, &accounts.saver_market_state);
+    let dex = &accounts.dex_market;
+    let owner = Pubkey::new_unique();
+    let wallet = SwapWallet::associated(&owner, dex);
+
+    let ix = accounts.swap(&wallet, &swap_params());
+    assert_eq!(
+        keys(&ix),
+        vec![
+            (accounts.saver, false, false),
+            (saver.signer, false, false),
+            (saver.srm_vault, false, true),
+            (accounts.saver_market, false, true),
+            (market.coin_vault, false, true),
+            (market.pc_vault, false, true),
+            (wallet.coin_wallet, false, true),
+            (wallet.pc_wallet, false, true),
+            (owner, true, false),
+            (dex.market, false, true),
+            (market.open_orders, false, true),
+            (dex.request_queue, false, true),
+            (dex.event_queue, false, true),
+            (dex.bids, false, true),
+            (dex.asks, false, true),
+            (dex.coin_vault, false, true),
+            (dex.pc_vault, false, true),
+            (dex.vault_signer, false, false),
+            (dex.program_id, false, false),
+            (spl_token::ID, false, false),
+        ]
+    );
+
+    let data: instruction::Swap = args(&ix, "swap");
+    assert!(matches!(Side::from(data.side), Side::Ask));
+    assert_eq!(data.limit_price, 198);
+    assert_eq!(data.max_coin_qty, 10);
+    assert_eq!(data.max_native_pc_qty_including_fees, 2_000);
+    assert_eq!(data.limit, 5);
+    assert_eq!(data.max_price_impact_bps, 30);
+    assert!(matches!(
+        SelfTradeBehavior::from(data.self_trade_behavior),
+        SelfTradeBehavior::CancelProvide
+    ));
+    assert_eq!(data.client_order_id, 7);
+}
+
+#[test]
+fn swap_optional_accounts() {
+    let mut accounts = accounts();
+    accounts.saver_market_state.oracle = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let signer = Pubkey::new_unique();
+    let wallet = SwapWallet::delegated(&owner, &signer, &accounts.dex_market)
+        .with_user_volume(&accounts.saver_market)
+        .with_allowlist_entry(&accounts.saver)
+        .with_user_stats(&accounts.saver);
+
+    // Order is fixed regardless of `with_*` calls: stats, allowlist entry, volume, oracle
+    let ix = accounts.swap(&wallet, &swap_params());
+    let metas = keys(&ix);
+    assert_eq!(metas[8], (signer, true, false));
+    assert_eq!(
+        metas[20..],
+        [
+            (get_user_stats(&accounts.saver, &owner).0, false, true),
+            (get_allowlist_entry(&accounts.saver, &owner).0, false, false),
+            (
+                get_user_volume(&accounts.saver_market, &owner).0,
+                false,
+                true
+            ),
+            (accounts.saver_market_state.oracle, false, false),
+        ]
+    );
+
+    let wallet =
+        SwapWallet::associated(&owner, &accounts.dex_market).with_user_stats(&accounts.saver);
+    accounts.saver_market_state.oracle = Pubkey::default();
+    let ix = accounts.swap(&wallet, &swap_params());
+    assert_eq!(
+        keys(&ix)[20..],
+        [(get_user_stats(&accounts.saver, &owner).0, false, true)]
+    );
+}
+
+#[test]
+fn crank() {
+    let accounts = accounts();
+    let tip_wallet = Pubkey::new_unique();
+
+    let ix = accounts.crank(&tip_wallet, 9);
+    assert_eq!(
+        keys(&ix),
+        vec![
+            (accounts.saver, false, false),
+            (accounts.saver_state.signer, false, false),
+            (accounts.saver_market, false, true),
+            (accounts.saver_market_state.pc_vault, false, true),
+            (tip_wallet, false, true),
+            (accounts.dex_market.market, false, true),
+            (accounts.saver_market_state.open_orders, false, true),
+            (accounts.dex_market.event_queue, false, true),
+            (accounts.dex_market.program_id, false, false),
+            (spl_token::ID, false, false),
+        ]
+    );
+    let data: instruction::Crank = args(&ix, "crank");
+    assert_eq!(data.limit, 9);
+}
+
+#[test]
+fn settle() {
+    let accounts = accounts();
+    let (market, dex) = (&accounts.saver_market_state, &accounts.dex_market);
+
+    let ix = accounts.settle();
+    assert_eq!(
+        keys(&ix),
+        vec![
+            (accounts.saver, false, false),
+            (accounts.saver_state.signer, false, false),
+            (accounts.saver_market, false, true),
+            (market.coin_vault, false, true),
+            (market.pc_vault, false, true),
+            (dex.market, false, true),
+            (market.open_orders, false, true),
+            (dex.coin_vault, false, true),
+            (dex.pc_vault, false, true),
+            (dex.vault_signer, false, false),
+            (dex.program_id, false, false),
+            (spl_token::ID, false, false),
+        ]
+    );
+    let _: instruction::Settle = args(&ix, "settle");
+}
+
+#[test]
+fn withdraw_fees() {
+    let accounts = accounts();
+    let (coin_wallet, pc_wallet) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let ix = accounts.withdraw_fees(&coin_wallet, &pc_wallet, 3, 4);
+    assert_eq!(
+        keys(&ix),
+        vec![
+            (accounts.saver, false, false),
+            (accounts.saver_state.signer, false, false),
+            (accounts.saver_state.authority, true, false),
+            (accounts.saver_market, false, true),
+            (accounts.saver_market_state.coin_vault, false, true),
+            (accounts.saver_market_state.pc_vault, false, true),
+            (coin_wallet, false, true),
+            (pc_wallet, false, true),
+            (spl_token::ID, false, false),
+        ]
+    );
+    let data: instruction::WithdrawFees = args(&ix, "withdraw_fees");
+    assert_eq!((data.coin_amount, data.pc_amount), (3, 4));
+}
+
+#[test]
+fn allowlist() {
+    let (saver, authority, wallet, payer) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let (allowlist_entry, nonce) = get_allowlist_entry(&saver, &wallet);
+    assert_eq!(
+        allowlist_entry,
+        Pubkey::create_program_address(
+            &[b"allowlist", saver.as_ref(), wallet.as_ref(), &[nonce]],
+            &serum_saver::ID
+        )
+        .unwrap()
+    );
+
+    let ix = add_to_allowlist(&saver, &authority, &wallet, &payer);
+    let metas = keys(&ix);
+    assert_eq!(metas[0].0, allowlist_entry);
+    assert_eq!(
+        metas[1..],
+        [
+            (saver, false, false),
+            (authority, true, false),
+            (wallet, false, false),
+            (payer, true, false),
+            (system_program::ID, false, false),
+        ]
+    );
+    let data: instruction::AddToAllowlist = args(&ix, "add_to_allowlist");
+    assert_eq!(data.nonce, nonce);
+
+    let ix = remove_from_allowlist(&saver, &authority, &wallet);
+    assert_eq!(
+        keys(&ix),
+        vec![
+            (allowlist_entry, false, true),
+            (saver, false, false),
+            (authority, true, true),
+        ]
+    );
+    let _: instruction::RemoveFromAllowlist = args(&ix, "remove_from_allowlist");
+}
+
+#[test]
+fn user_volume() {
+    let accounts = accounts();
+    let (wallet, payer) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (user_volume, nonce) = get_user_volume(&accounts.saver_market, &wallet);
+    assert_eq!(
+        user_volume,
+        Pubkey::create_program_address(
+            &[
+                b"volume",
+                accounts.saver_market.as_ref(),
+                wallet.as_ref(),
+                &[nonce]
+            ],
+            &serum_saver::ID
+        )
+        .unwrap()
+    );
+
+    let ix = accounts.initialize_user_volume(&wallet, &payer);
+    let metas = keys(&ix);
+    assert_eq!(metas[0].0, user_volume);
+    assert_eq!(
+        metas[1..],
+        [
+            (accounts.saver_market, false, false),
+            (wallet, false, false),
+            (payer, true, false),
+            (system_program::ID, false, false),
+        ]
+    );
+    let data: instruction::InitializeUserVolume = args(&ix, "initialize_user_volume");
+    assert_eq!(data.nonce, nonce);
+}
+
+#[test]
+fn msrm_vault() {
+    let (saver, dex_program, payer) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let (signer, nonce) = get_saver_signer(&saver);
+    let srm_vault = get_associated_token_address(&signer, &msrm_token::ID);
+
+    let ix = create_srm_vault(&saver, &msrm_token::ID, &payer);
+    assert_eq!(ix.program_id, spl_associated_token_account::ID);
+    assert_eq!(
+        ix.accounts
+            .iter()
+            .map(|meta| meta.pubkey)
+            .take(4)
+            .collect::<Vec<_>>(),
+        vec![payer, srm_vault, signer, msrm_token::ID]
+    );
+
+    let ix = initialize_saver(
+        &saver,
+        &msrm_token::ID,
+        &dex_program,
+        &payer,
+        &payer,
+        serum_dex::fees::FeeTier::MSRM,
+    );
+    assert_eq!(keys(&ix)[2], (srm_vault, false, false));
+    let data: instruction::InitializeSaver = args(&ix, "initialize_saver");
+    assert_eq!(data.nonce, nonce);
+}
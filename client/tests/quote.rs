@@ -0,0 +1,141 @@
+use serum_dex::{fees::FeeTier, instruction::SelfTradeBehavior, matching::Side};
+use serum_saver_client::{
+    quote::{Order, Orderbook},
+    SwapParams,
+};
+
+fn orderbook() -> Orderbook {
+    Orderbook {
+        coin_lot_size: 1_000,
+        pc_lot_size: 10,
+        bids: vec![
+            Order {
+                price: 198,
+                quantity: 1_000,
+            },
+            Order {
+                price: 196,
+                quantity: 1_000,
+            },
+        ],
+        asks: vec![
+            Order {
+                price: 202,
+                quantity: 1_000,
+            },
+            Order {
+                price: 204,
+                quantity: 1_000,
+            },
+        ],
+    }
+}
+
+#[test]
+fn quote_bid_walks_asks_up_to_limit_price() {
+    let quote = orderbook().quote(
+        FeeTier::Base,
+        &SwapParams {
+            side: Side::Bid,
+            limit_price: 204,
+            max_coin_qty: 1_500,
+            max_native_pc_qty_including_fees: u64::MAX / 10_000,
+            limit: u16::MAX,
+            max_price_impact_bps: 0,
+            self_trade_behavior: SelfTradeBehavior::AbortTransaction,
+            client_order_id: 0,
+        },
+    );
+
+    let native_pc_qty = (1_000 * 202 + 500 * 204) * 10;
+    let fee = native_pc_qty * 22 / 10_000;
+    assert_eq!(quote.native_coin_qty, 1_500 * 1_000);
+    assert_eq!(quote.native_pc_qty, native_pc_qty + fee);
+    assert_eq!(quote.fee, fee);
+    assert_eq!(quote.fee_saved, 0);
+}
+
+#[test]
+fn quote_ask_stops_at_limit_price() {
+    let quote = orderbook().quote(
+        FeeTier::SRM2,
+        &SwapParams {
+            side: Side::Ask,
+            limit_price: 197,
+            max_coin_qty: 1_500,
+            max_native_pc_qty_including_fees: 1,
+            limit: u16::MAX,
+            max_price_impact_bps: 0,
+            self_trade_behavior: SelfTradeBehavior::AbortTransaction,
+            client_order_id: 0,
+        },
+    );
+
+    let native_pc_qty = 1_000 * 198 * 10;
+    assert_eq!(quote.native_coin_qty, 1_000 * 1_000);
+    assert_eq!(
+        quote.native_pc_qty,
+        native_pc_qty - native_pc_qty * 20 / 10_000
+    );
+    assert_eq!(quote.fee_saved, native_pc_qty * 2 / 10_000);
+    assert!((quote.average_price - 198.0).abs() < f64::EPSILON);
+}
+
+#[test]
+fn quote_bid_limited_by_pc_qty() {
+    let quote = orderbook().quote(
+        FeeTier::Base,
+        &SwapParams {
+            side: Side::Bid,
+            limit_price: 204,
+            max_coin_qty: 1_500,
+            // 100 lots at 202 with 22 bps fee
+            max_native_pc_qty_including_fees: 202_000 + 445,
+            limit: u16::MAX,
+            max_price_impact_bps: 0,
+            self_trade_behavior: SelfTradeBehavior::AbortTransaction,
+            client_order_id: 0,
+        },
+    );
+
+    assert_eq!(quote.native_coin_qty, 100 * 1_000);
+}
+
+#[test]
+fn quote_bid_stops_at_price_impact() {
+    let quote = orderbook().quote(
+        FeeTier::Base,
+        &SwapParams {
+            side: Side::Bid,
+            limit_price: 204,
+            max_coin_qty: 1_500,
+            max_native_pc_qty_including_fees: u64::MAX / 10_000,
+            limit: u16::MAX,
+            // 202 * 1.005 = 203.01
+            max_price_impact_bps: 50,
+            self_trade_behavior: SelfTradeBehavior::AbortTransaction,
+            client_order_id: 0,
+        },
+    );
+
+    assert_eq!(quote.native_coin_qty, 1_000 * 1_000);
+}
+
+#[test]
+fn quote_ask_stops_at_order_limit() {
+    let quote = orderbook().quote(
+        FeeTier::Base,
+        &SwapParams {
+            side: Side::Ask,
+            limit_price: 196,
+            max_coin_qty: 1_500,
+            max_native_pc_qty_including_fees: 1,
+            limit: 1,
+            max_price_impact_bps: 0,
+            self_trade_behavior: SelfTradeBehavior::AbortTransaction,
+            client_order_id: 0,
+        },
+    );
+
+    assert_eq!(quote.native_coin_qty, 1_000 * 1_000);
+}
@@ -1,30 +1,32 @@
 use anchor_lang::prelude::*;
-use serum_dex::{matching::Side, state::MarketState};
-use solana_program::program::invoke_signed;
-use std::convert::TryFrom;
+use serum_dex::{
+    critbit::{AnyNode, Slab, SlabView},
+    fees::FeeTier,
+    instruction::{msrm_token, MarketInstruction, SelfTradeBehavior},
+    matching::Side,
+    state::{Market, ToAlignedBytes},
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+};
+use std::{
+    convert::{TryFrom, TryInto},
+    mem::{size_of, size_of_val},
+};
 
-#[cfg(feature = "devnet")]
-declare_id!("DESVgJVGajEgKGXhb6XmqDHGz3VjdgP7rEVESBgxmroY");
-#[cfg(not(feature = "devnet"))]
-declare_id!("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin");
-
-#[derive(Debug, Clone, Copy)]
-pub struct Dex;
+use crate::{error::SaverError, token::TokenAccount};
 
-impl anchor_lang::AccountDeserialize for Dex {
-    fn try_deserialize(buf: &mut &[u8]) -> Result<Self, ProgramError> {
-        Self::try_deserialize_unchecked(buf)
-    }
+// serum-dex v0.4 mainnet deploy. `Saver` can use any DEX program set at initialization, well-known
+// ids are defaults for clients and tests.
+declare_id!("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin");
 
-    fn try_deserialize_unchecked(_buf: &mut &[u8]) -> Result<Self, ProgramError> {
-        Ok(Self)
-    }
-}
+/// OpenBook v1 mainnet deploy, fork of serum-dex v0.5. Instructions to it always use v0.5 layout,
+/// see `adapter`.
+pub mod openbook {
+    use anchor_lang::prelude::*;
 
-impl anchor_lang::Id for Dex {
-    fn id() -> Pubkey {
-        ID
-    }
+    declare_id!("srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX");
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -62,39 +64,482 @@ impl From<Side> for SideAnchor {
     }
 }
 
-pub fn get_market_coin_lot_size(market: &AccountInfo<'_>) -> Result<u64, ProgramError> {
-    Ok(MarketState::load(market, &ID)?.coin_lot_size)
+#[derive(Debug, Clone, Copy)]
+pub struct SelfTradeBehaviorAnchor(SelfTradeBehavior);
+
+impl AnchorDeserialize for SelfTradeBehaviorAnchor {
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, std::io::Error> {
+        let number: u8 = AnchorDeserialize::deserialize(buf)?;
+        match SelfTradeBehavior::try_from(number) {
+            Ok(self_trade_behavior) => Ok(Self(self_trade_behavior)),
+            Err(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "No discriminant in enum matches the value",
+            )),
+        }
+    }
+}
+
+impl AnchorSerialize for SelfTradeBehaviorAnchor {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<(), std::io::Error> {
+        let number: u8 = self.0.into();
+        number.serialize(writer)
+    }
+}
+
+impl From<SelfTradeBehaviorAnchor> for SelfTradeBehavior {
+    fn from(self_trade_behavior: SelfTradeBehaviorAnchor) -> Self {
+        self_trade_behavior.0
+    }
+}
+
+impl From<SelfTradeBehavior> for SelfTradeBehaviorAnchor {
+    fn from(self_trade_behavior: SelfTradeBehavior) -> Self {
+        SelfTradeBehaviorAnchor(self_trade_behavior)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FeeTierAnchor(FeeTier);
+
+impl AnchorDeserialize for FeeTierAnchor {
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, std::io::Error> {
+        let number: u8 = AnchorDeserialize::deserialize(buf)?;
+        match FeeTier::try_from(number) {
+            Ok(fee_tier) => Ok(Self(fee_tier)),
+            Err(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "No discriminant in enum matches the value",
+            )),
+        }
+    }
+}
+
+impl AnchorSerialize for FeeTierAnchor {
+    fn serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<(), std::io::Error> {
+        let number = self.0 as u8;
+        number.serialize(writer)
+    }
+}
+
+impl From<FeeTierAnchor> for FeeTier {
+    fn from(fee_tier: FeeTierAnchor) -> Self {
+        fee_tier.0
+    }
+}
+
+impl From<FeeTier> for FeeTierAnchor {
+    fn from(fee_tier: FeeTier) -> Self {
+        FeeTierAnchor(fee_tier)
+    }
+}
+
+/// Coin and pc lot sizes of Serum market.
+pub fn get_market_lot_sizes(
+    program_id: &Pubkey,
+    market: &AccountInfo<'_>,
+) -> Result<(u64, u64), ProgramError> {
+    let market = Market::load(market, program_id)?;
+    Ok((market.coin_lot_size, market.pc_lot_size))
+}
+
+/// Coin and pc mints of Serum market.
+pub fn get_market_mints(
+    program_id: &Pubkey,
+    market: &AccountInfo<'_>,
+) -> Result<(Pubkey, Pubkey), ProgramError> {
+    let market = Market::load(market, program_id)?;
+    Ok((to_pubkey(market.coin_mint), to_pubkey(market.pc_mint)))
 }
 
+fn to_pubkey(words: [u64; 4]) -> Pubkey {
+    let mut bytes = [0; 32];
+    for (chunk, word) in bytes.chunks_exact_mut(8).zip(words.iter()) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    Pubkey::new(&bytes)
+}
+
+/// Authorities of permissioned market. serum-dex v0.4 has no separate consume events authority,
+/// `prune_authority` signs `ConsumeEventsPermissioned` too. Separate consume events authority of
+/// serum-dex v0.5 / OpenBook v1 is not read, such markets should have it equal to prune authority.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarketAuthorities {
+    pub open_orders: Pubkey,
+    pub prune: Pubkey,
+}
+
+/// Authorities of permissioned market, `None` for regular market.
+pub fn get_market_authorities(
+    program_id: &Pubkey,
+    market: &AccountInfo<'_>,
+) -> Result<Option<MarketAuthorities>, ProgramError> {
+    Ok(match Market::load(market, program_id)? {
+        Market::V1(_) => None,
+        Market::V2(state) => Some(MarketAuthorities {
+            open_orders: state.open_orders_authority,
+            prune: state.prune_authority,
+        }),
+    })
+}
+
+/// Saver can use permissioned market only if `signer` is both open orders and prune authority,
+/// otherwise saver `OpenOrders` can not be created or cranked by the saver. Returns `true` for
+/// permissioned market, then `signer` should sign as market authority.
+pub fn check_market_authorities(
+    program_id: &Pubkey,
+    market: &AccountInfo<'_>,
+    signer: &Pubkey,
+) -> Result<bool, ProgramError> {
+    match get_market_authorities(program_id, market)? {
+        None => Ok(false),
+        Some(authorities) if authorities.open_orders != *signer => {
+            Err(SaverError::InvalidOpenOrdersAuthority.into())
+        }
+        Some(authorities) if authorities.prune != *signer => {
+            Err(SaverError::InvalidPruneAuthority.into())
+        }
+        Some(_) => Ok(true),
+    }
+}
+
+/// `ConsumeEventsPermissioned` has the same layout in serum-dex v0.4, v0.5 and OpenBook v1.
+pub fn consume_events_permissioned(
+    program_id: &Pubkey,
+    open_orders: &[&Pubkey],
+    market: &Pubkey,
+    event_queue: &Pubkey,
+    consume_events_authority: &Pubkey,
+    limit: u16,
+) -> Instruction {
+    let mut accounts = open_orders
+        .iter()
+        .map(|key| AccountMeta::new(**key, false))
+        .collect::<Vec<_>>();
+    accounts.extend_from_slice(&[
+        AccountMeta::new(*market, false),
+        AccountMeta::new(*event_queue, false),
+        AccountMeta::new_readonly(*consume_events_authority, true),
+    ]);
+    Instruction {
+        program_id: *program_id,
+        data: MarketInstruction::ConsumeEventsPermissioned(limit).pack(),
+        accounts,
+    }
+}
+
+/// `consume_events` looks up `OpenOrders` of every event with binary search by aligned key, so
+/// accounts should be sorted in this order and unique.
+pub fn sort_open_orders(open_orders: &mut Vec<Pubkey>) {
+    open_orders.sort_by_key(|key| key.to_aligned_bytes());
+    open_orders.dedup();
+}
+
+/// Number of events in the queue, from raw header:
+/// `"serum" || account_flags || head || count || seq_num || events || "padding"`.
+pub fn get_event_queue_count(event_queue: &AccountInfo<'_>) -> Result<u64, ProgramError> {
+    event_queue
+        .try_borrow_data()?
+        .get(21..29)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+/// Fee tier of the saver with Serum fee schedule, all rates are computed by `FeeTier` itself, so
+/// they are always the same as in `new_order`.
+#[derive(Debug, Clone, Copy)]
+pub struct FeeRates {
+    pub fee_tier: FeeTier,
+}
+
+impl FeeRates {
+    pub fn new(fee_tier: FeeTier) -> Self {
+        Self { fee_tier }
+    }
+
+    /// Taker fee rounded up.
+    pub fn taker_fee(&self, native_pc_qty: u64) -> u64 {
+        self.fee_tier.taker_fee(native_pc_qty)
+    }
+
+    /// Native pc quantity without taker fee, rounded down.
+    pub fn remove_taker_fee(&self, native_pc_qty_including_fees: u64) -> u64 {
+        self.fee_tier.remove_taker_fee(native_pc_qty_including_fees)
+    }
+
+    pub fn maker_rebate(&self, native_pc_qty: u64) -> u64 {
+        self.fee_tier.maker_rebate(native_pc_qty)
+    }
+}
+
+/// Fee rates of the saver on `market` by balance of `Saver::srm_vault`, which can hold SRM or
+/// MSRM. Serum gives `Stable` tier on stable markets regardless of balance.
+pub fn get_fee_rates(market: &Pubkey, srm_vault: &TokenAccount) -> FeeRates {
+    let (srm_balance, msrm_balance) = if srm_vault.mint == msrm_token::ID {
+        (0, srm_vault.amount)
+    } else {
+        (srm_vault.amount, 0)
+    };
+    FeeRates::new(FeeTier::from_srm_and_msrm_balances(
+        market,
+        srm_balance,
+        msrm_balance,
+    ))
+}
+
+/// Taker fee which would be paid on `Base` tier minus fee paid on `fee_tier`, zero on stable
+/// markets where every taker is on `Stable` tier. `native_pc_qty` is matched quantity without
+/// fees, e.g. of `simulate_fill`, so both sides are exact.
+pub fn get_taker_fee_saved(fee_tier: FeeTier, native_pc_qty: u64) -> u64 {
+    if let FeeTier::Stable = fee_tier {
+        return 0;
+    }
+    FeeTier::Base
+        .taker_fee(native_pc_qty)
+        .saturating_sub(fee_tier.taker_fee(native_pc_qty))
+}
+
+/// Resting order, price and quantity in lots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Order {
+    pub price: u64,
+    pub quantity: u64,
+}
+
+// Slab header and inner node layout, serum-dex does not expose root and children of the tree.
+// https://github.com/project-serum/serum-dex/blob/v0.4.0/dex/src/critbit.rs
+const SLAB_HEADER_LEN: usize = 32;
+const SLAB_ROOT_OFFSET: usize = 20;
+const SLAB_LEAF_COUNT_OFFSET: usize = 24;
+const INNER_NODE_CHILDREN_OFFSET: usize = 24;
+
+fn slab_bytes(slab: &Slab) -> &[u8] {
+    // `Slab` is `repr(transparent)` over the account bytes.
+    unsafe { std::slice::from_raw_parts(slab as *const Slab as *const u8, size_of_val(slab)) }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// Resting orders of `side` of the book from the best price: bids from the highest price, asks
+/// from the lowest. Keys of the critbit tree are price and sequence number, so in-order walk
+/// gives the same priority as matching and callers can stop at the first order they don't need.
+pub fn iter_orders(slab: &Slab, side: Side) -> impl Iterator<Item = Order> + '_ {
+    let bytes = slab_bytes(slab);
+    let leaf_count = u64::from_le_bytes(
+        bytes[SLAB_LEAF_COUNT_OFFSET..SLAB_LEAF_COUNT_OFFSET + 8]
+            .try_into()
+            .unwrap(),
+    );
+    // Stack of subtrees to visit, its depth is bounded by key length.
+    let mut stack = Vec::with_capacity(32);
+    if leaf_count > 0 {
+        stack.push(read_u32(bytes, SLAB_ROOT_OFFSET));
+    }
+    let (first, second) = match side {
+        Side::Bid => (1, 0),
+        Side::Ask => (0, 1),
+    };
+
+    std::iter::from_fn(move || loop {
+        let handle = stack.pop()?;
+        let node = slab.get(handle)?;
+        if let Some(leaf) = node.as_leaf() {
+            return Some(Order {
+                price: leaf.price().get(),
+                quantity: leaf.quantity(),
+            });
+        }
+
+        let offset =
+            SLAB_HEADER_LEN + handle as usize * size_of::<AnyNode>() + INNER_NODE_CHILDREN_OFFSET;
+        stack.push(read_u32(bytes, offset + 4 * second));
+        stack.push(read_u32(bytes, offset + 4 * first));
+    })
+}
+
+/// Orders which can be matched by taker on `side` with `limit_price`, sorted from best price.
+/// `limit` is max number of matched orders, same as in `new_order`, so the rest of the book is
+/// not visited.
+pub fn load_crossed_orders(
+    program_id: &Pubkey,
+    market: &AccountInfo<'_>,
+    bids: &AccountInfo<'_>,
+    asks: &AccountInfo<'_>,
+    side: Side,
+    limit_price: u64,
+    limit: u16,
+) -> Result<Vec<Order>, ProgramError> {
+    let market = Market::load(market, program_id)?;
+    let orders = match side {
+        Side::Bid => iter_orders(&market.load_asks_mut(asks)?, Side::Ask)
+            .take_while(|order| order.price <= limit_price)
+            .take(limit as usize)
+            .collect(),
+        Side::Ask => iter_orders(&market.load_bids_mut(bids)?, Side::Bid)
+            .take_while(|order| order.price >= limit_price)
+            .take(limit as usize)
+            .collect(),
+    };
+    Ok(orders)
+}
+
+/// Limit price which does not let taker on `side` walk the book further than
+/// `max_price_impact_bps` from `best_price`, zero means no cap.
+pub fn clamp_limit_price(
+    side: Side,
+    limit_price: u64,
+    best_price: u64,
+    max_price_impact_bps: u64,
+) -> u64 {
+    if max_price_impact_bps == 0 {
+        return limit_price;
+    }
+
+    let best_price = best_price as u128;
+    let bps = max_price_impact_bps as u128;
+    match side {
+        Side::Bid => {
+            let max_price = best_price * (10_000 + bps) / 10_000;
+            limit_price.min(max_price.min(u64::MAX as u128) as u64)
+        }
+        Side::Ask => {
+            let min_price = (best_price * 10_000u128.saturating_sub(bps) + 9_999) / 10_000;
+            limit_price.max(min_price as u64)
+        }
+    }
+}
+
+/// Lots matched by immediate-or-cancel taker order, without fee.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Fill {
+    pub coin_qty: u64,
+    pub pc_qty: u64,
+}
+
+/// Match taker order on `side` against `orders` sorted from best price, `max_pc_qty` in lots.
+/// `limit` is max number of matched orders, same as in `new_order`.
+pub fn simulate_fill(
+    orders: &[Order],
+    side: Side,
+    limit_price: u64,
+    max_coin_qty: u64,
+    max_pc_qty: u64,
+    limit: u16,
+) -> Fill {
+    let mut fill = Fill::default();
+    for order in orders.iter().take(limit as usize) {
+        let crossed = match side {
+            Side::Bid => order.price <= limit_price,
+            Side::Ask => order.price >= limit_price,
+        };
+        if !crossed {
+            break;
+        }
+
+        let qty = order
+            .quantity
+            .min(max_coin_qty - fill.coin_qty)
+            .min((max_pc_qty - fill.pc_qty) / order.price);
+        if qty == 0 {
+            break;
+        }
+
+        fill.coin_qty += qty;
+        fill.pc_qty += qty * order.price;
+    }
+    fill
+}
+
+/// Permissioned market (e.g. behind a permissioned-market proxy) should have `owner` as
+/// `open_orders_authority` and `prune_authority`, then `owner` signs as market authority too.
 pub fn init_open_orders<'info>(
     dex_program: AccountInfo<'info>,
     open_orders: AccountInfo<'info>,
     owner: AccountInfo<'info>,
     market: AccountInfo<'info>,
+    rent: AccountInfo<'info>,
     seeds: &[&[&[u8]]],
 ) -> ProgramResult {
+    let permissioned = check_market_authorities(dex_program.key, &market, owner.key)?;
+
     invoke_signed(
-        &instruction_patched::init_open_orders(
+        &adapter::init_open_orders(
             dex_program.key,
             open_orders.key,
             owner.key,
             market.key,
-            None,
+            Some(owner.key).filter(|_| permissioned),
         )?,
-        &[open_orders, owner, market],
+        &[open_orders, owner, market, rent],
         seeds,
     )
 }
 
-// v0.4.0 start use dynamic sysvars but keys still need to be passed
-// Need to be reviewed before `serum-dex` update!
-// https://github.com/project-serum/serum-dex/blob/v0.4.0/dex/src/instruction.rs#L909-L931
-mod instruction_patched {
-    use serum_dex::{error::DexError, instruction::MarketInstruction};
-    use solana_program::{
-        instruction::{AccountMeta, Instruction},
-        pubkey::Pubkey,
-    };
+/// `settle_funds` of saver `OpenOrders` to saver vaults. Referrer rebates accrued by consumed
+/// taker events go to `pc_vault` as well, so every settle of the saver pays them the same way.
+#[allow(clippy::too_many_arguments)]
+pub fn settle_funds<'info>(
+    dex_program: AccountInfo<'info>,
+    market: AccountInfo<'info>,
+    open_orders: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    dex_coin_vault: AccountInfo<'info>,
+    dex_pc_vault: AccountInfo<'info>,
+    coin_vault: AccountInfo<'info>,
+    pc_vault: AccountInfo<'info>,
+    dex_vault_signer: AccountInfo<'info>,
+    spl_token_program: AccountInfo<'info>,
+    seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &adapter::settle_funds(
+            dex_program.key,
+            market.key,
+            spl_token_program.key,
+            open_orders.key,
+            owner.key,
+            dex_coin_vault.key,
+            coin_vault.key,
+            dex_pc_vault.key,
+            pc_vault.key,
+            Some(pc_vault.key),
+            dex_vault_signer.key,
+        )
+        .map_err(|e| -> ProgramError { e.into() })?,
+        &[
+            market,
+            open_orders,
+            owner,
+            dex_coin_vault,
+            dex_pc_vault,
+            coin_vault,
+            pc_vault,
+            dex_vault_signer,
+            spl_token_program,
+        ],
+        seeds,
+    )
+}
+
+/// Instructions to DEX programs, saver calls DEX only through this module. Layout is chosen by DEX
+/// program id only, so one build serves markets of serum-dex v0.4 and OpenBook v1: OpenBook
+/// mainnet id uses v0.5 layout, any other id uses v0.4 layout. Layouts should be reviewed before
+/// every DEX update.
+pub mod adapter {
+    use serum_dex::error::DexError;
+    use solana_program::{instruction::Instruction, pubkey::Pubkey};
+
+    /// `NewOrderV3`, `SettleFunds` and `ConsumeEvents` take the same accounts in v0.4 and v0.5, and
+    /// v0.5 accepts `NewOrderV3` data without trailing `max_ts`, so v0.4 builders serve both.
+    /// Layouts are checked against OpenBook in `tests/dex_adapter.rs`.
+    pub use serum_dex::instruction::{consume_events, new_order, settle_funds};
+
+    pub fn is_v5(program_id: &Pubkey) -> bool {
+        *program_id == super::openbook::ID
+    }
 
     pub fn init_open_orders(
         program_id: &Pubkey,
@@ -103,21 +548,81 @@ mod instruction_patched {
         market: &Pubkey,
         market_authority: Option<&Pubkey>,
     ) -> Result<Instruction, DexError> {
-        let data = MarketInstruction::InitOpenOrders.pack();
-        let mut accounts: Vec<AccountMeta> = vec![
-            AccountMeta::new(*open_orders, false),
-            AccountMeta::new_readonly(*owner, true),
-            AccountMeta::new_readonly(*market, false),
-            // AccountMeta::new_readonly(rent::ID, false),
-            AccountMeta::new_readonly(*market, false),
-        ];
-        if let Some(market_authority) = market_authority {
-            accounts.push(AccountMeta::new_readonly(*market_authority, true));
+        if is_v5(program_id) {
+            v5::init_open_orders(program_id, open_orders, owner, market, market_authority)
+        } else {
+            v4::init_open_orders(program_id, open_orders, owner, market, market_authority)
+        }
+    }
+
+    /// serum-dex v0.4.0 use dynamic sysvars, but keys still need to be passed, so market is passed
+    /// instead of rent.
+    /// https://github.com/project-serum/serum-dex/blob/v0.4.0/dex/src/instruction.rs#L909-L931
+    pub mod v4 {
+        use serum_dex::{error::DexError, instruction::MarketInstruction};
+        use solana_program::{
+            instruction::{AccountMeta, Instruction},
+            pubkey::Pubkey,
+        };
+
+        pub fn init_open_orders(
+            program_id: &Pubkey,
+            open_orders: &Pubkey,
+            owner: &Pubkey,
+            market: &Pubkey,
+            market_authority: Option<&Pubkey>,
+        ) -> Result<Instruction, DexError> {
+            let data = MarketInstruction::InitOpenOrders.pack();
+            let mut accounts: Vec<AccountMeta> = vec![
+                AccountMeta::new(*open_orders, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(*market, false),
+                // AccountMeta::new_readonly(rent::ID, false),
+                AccountMeta::new_readonly(*market, false),
+            ];
+            if let Some(market_authority) = market_authority {
+                accounts.push(AccountMeta::new_readonly(*market_authority, true));
+            }
+            Ok(Instruction {
+                program_id: *program_id,
+                data,
+                accounts,
+            })
+        }
+    }
+
+    /// serum-dex v0.5 and OpenBook v1 (fork of serum-dex v0.5) expect rent sysvar at its place.
+    /// https://github.com/openbook-dex/program/blob/master/dex/src/instruction.rs
+    pub mod v5 {
+        use serum_dex::{error::DexError, instruction::MarketInstruction};
+        use solana_program::{
+            instruction::{AccountMeta, Instruction},
+            pubkey::Pubkey,
+            sysvar::rent,
+        };
+
+        pub fn init_open_orders(
+            program_id: &Pubkey,
+            open_orders: &Pubkey,
+            owner: &Pubkey,
+            market: &Pubkey,
+            market_authority: Option<&Pubkey>,
+        ) -> Result<Instruction, DexError> {
+            let data = MarketInstruction::InitOpenOrders.pack();
+            let mut accounts: Vec<AccountMeta> = vec![
+                AccountMeta::new(*open_orders, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(*market, false),
+                AccountMeta::new_readonly(rent::ID, false),
+            ];
+            if let Some(market_authority) = market_authority {
+                accounts.push(AccountMeta::new_readonly(*market_authority, true));
+            }
+            Ok(Instruction {
+                program_id: *program_id,
+                data,
+                accounts,
+            })
         }
-        Ok(Instruction {
-            program_id: *program_id,
-            data,
-            accounts,
-        })
     }
 }
@@ -8,4 +8,56 @@ pub enum SaverError {
     CoinQtyOverflow,
     #[msg("Amount should be greater than zero")]
     NonZeroU64,
+    #[msg("Coin vault balance decreased during swap")]
+    CoinBalanceChangeUnderflow,
+    #[msg("Pc vault balance decreased during swap")]
+    PcBalanceChangeUnderflow,
+    #[msg("Vault balance after swap is not equal to balance before swap")]
+    VaultBalanceMismatch,
+    #[msg("UserStats does not belong to Saver or wallet")]
+    InvalidUserStats,
+    #[msg("Saver fee tier is below minimum fee tier")]
+    FeeTierTooLow,
+    #[msg("Swap pc_qty is overflow")]
+    PcQtyOverflow,
+    #[msg("No orders to match within limit price")]
+    NoLiquidity,
+    #[msg("Wallet signer is not owner or approved delegate of the wallet")]
+    InvalidWalletAuthority,
+    #[msg("Delegated swap should pay out to wallets of the same owner")]
+    InvalidWalletOwner,
+    #[msg("Wallet is not in Saver allowlist")]
+    WalletNotAllowed,
+    #[msg("Swap notional is above SaverMarket limit")]
+    MaxNotionalExceeded,
+    #[msg("Too many swaps through SaverMarket in this slot")]
+    MaxSwapsPerSlotExceeded,
+    #[msg("Wallet daily volume is above SaverMarket limit")]
+    MaxDailyVolumeExceeded,
+    #[msg("UserVolume of wallet is required by SaverMarket daily volume limit")]
+    UserVolumeRequired,
+    #[msg("Oracle is not a Pyth price account")]
+    InvalidOracle,
+    #[msg("Oracle price is not available")]
+    OraclePriceUnavailable,
+    #[msg("Oracle account of SaverMarket is required")]
+    OracleRequired,
+    #[msg("Swap average price is out of oracle price band")]
+    OraclePriceBandExceeded,
+    #[msg("Overflow in oracle price band check")]
+    OracleMathOverflow,
+    #[msg("Permissioned market open orders authority is not saver signer")]
+    InvalidOpenOrdersAuthority,
+    #[msg("Mint is not coin or pc mint of the market")]
+    InvalidMarketMint,
+    #[msg("Oracle price is older than SaverMarket max oracle age")]
+    OraclePriceStale,
+    #[msg("Oracle confidence interval is wider than SaverMarket price band")]
+    OracleConfidenceTooWide,
+    #[msg("Tip wallet mint is not pc mint of the market")]
+    InvalidTipWallet,
+    #[msg("Withdraw amount is above collected SaverMarket fees")]
+    WithdrawExceedsFees,
+    #[msg("Permissioned market prune (consume events) authority is not saver signer")]
+    InvalidPruneAuthority,
 }
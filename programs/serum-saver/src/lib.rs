@@ -1,16 +1,25 @@
-use anchor_lang::prelude::*;
-use serum_dex::{instruction::srm_token, matching::Side};
-use solana_program::program::invoke_signed;
+use anchor_lang::{prelude::*, AccountsExit};
+use serum_dex::{
+    fees::FeeTier,
+    instruction::{msrm_token, srm_token, SelfTradeBehavior},
+    matching::Side,
+};
+use solana_program::program::{invoke, invoke_signed};
 use spl_associated_token_account::get_associated_token_address as gata;
 use std::num::NonZeroU64;
 use {
-    dex::{get_market_coin_lot_size, Dex, SideAnchor},
+    dex::{
+        clamp_limit_price, get_event_queue_count, get_fee_rates, get_market_lot_sizes,
+        get_market_mints, get_taker_fee_saved, load_crossed_orders, simulate_fill, FeeTierAnchor,
+        SelfTradeBehaviorAnchor, SideAnchor,
+    },
     error::{SaverError, SaverResult},
     token::{SplToken, TokenAccount, TokenAccountState, TokenMint},
 };
 
 pub mod dex;
 pub mod error;
+pub mod oracle;
 pub mod token;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
@@ -19,37 +28,135 @@ declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 pub mod serum_saver {
     use super::*;
 
-    pub fn initialize_saver(ctx: Context<InitializeSaver>, nonce: u8) -> SaverResult {
+    pub fn initialize_saver(
+        ctx: Context<InitializeSaver>,
+        nonce: u8,
+        min_fee_tier: FeeTierAnchor,
+    ) -> SaverResult {
+        ctx.accounts.handle(nonce, min_fee_tier.into())
+    }
+
+    pub fn set_min_fee_tier(
+        ctx: Context<SetMinFeeTier>,
+        min_fee_tier: FeeTierAnchor,
+    ) -> SaverResult {
+        ctx.accounts.handle(min_fee_tier.into())
+    }
+
+    pub fn set_allowlist_enabled(ctx: Context<SetAllowlistEnabled>, enabled: bool) -> SaverResult {
+        ctx.accounts.handle(enabled)
+    }
+
+    pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, nonce: u8) -> SaverResult {
         ctx.accounts.handle(nonce)
     }
 
-    pub fn initialize_market(ctx: Context<InitializeMarket>) -> SaverResult {
-        ctx.accounts.handle()
+    pub fn remove_from_allowlist(_ctx: Context<RemoveFromAllowlist>) -> SaverResult {
+        Ok(())
+    }
+
+    pub fn initialize_market(
+        ctx: Context<InitializeMarket>,
+        coin_vault_nonce: u8,
+        pc_vault_nonce: u8,
+    ) -> SaverResult {
+        ctx.accounts.handle(coin_vault_nonce, pc_vault_nonce)
+    }
+
+    pub fn set_market_limits(
+        ctx: Context<SetMarketLimits>,
+        max_notional_per_swap: u64,
+        max_swaps_per_slot: u64,
+        max_daily_volume_per_user: u64,
+    ) -> SaverResult {
+        ctx.accounts.handle(
+            max_notional_per_swap,
+            max_swaps_per_slot,
+            max_daily_volume_per_user,
+        )
+    }
+
+    pub fn set_market_oracle(
+        ctx: Context<SetMarketOracle>,
+        oracle: Pubkey,
+        oracle_band_bps: u64,
+        max_oracle_age_slots: u64,
+    ) -> SaverResult {
+        ctx.accounts
+            .handle(oracle, oracle_band_bps, max_oracle_age_slots)
+    }
+
+    pub fn set_crank_tip(ctx: Context<SetCrankTip>, crank_tip: u64) -> SaverResult {
+        ctx.accounts.handle(crank_tip)
+    }
+
+    pub fn initialize_user_stats(ctx: Context<InitializeUserStats>, nonce: u8) -> SaverResult {
+        ctx.accounts.handle(nonce)
+    }
+
+    pub fn initialize_user_volume(ctx: Context<InitializeUserVolume>, nonce: u8) -> SaverResult {
+        ctx.accounts.handle(nonce)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn swap(
         ctx: Context<Swap>,
         side: SideAnchor,
         limit_price: u64,
         max_coin_qty: u64,
         max_native_pc_qty_including_fees: u64,
+        limit: u16,
+        max_price_impact_bps: u64,
+        self_trade_behavior: SelfTradeBehaviorAnchor,
+        client_order_id: u64,
     ) -> SaverResult {
         ctx.accounts.handle(
+            ctx.remaining_accounts,
             side.into(),
             limit_price,
             max_coin_qty,
             max_native_pc_qty_including_fees,
+            limit,
+            max_price_impact_bps,
+            self_trade_behavior.into(),
+            client_order_id,
         )
     }
+
+    pub fn crank(ctx: Context<Crank>, limit: u16) -> SaverResult {
+        ctx.accounts.handle(limit)
+    }
+
+    pub fn settle(ctx: Context<Settle>) -> SaverResult {
+        ctx.accounts.handle()
+    }
+
+    pub fn withdraw_fees(
+        ctx: Context<WithdrawFees>,
+        coin_amount: u64,
+        pc_amount: u64,
+    ) -> SaverResult {
+        ctx.accounts.handle(coin_amount, pc_amount)
+    }
 }
 
 #[account]
 #[derive(Debug, Default, Copy)]
 pub struct Saver {
+    pub authority: Pubkey,
+
     pub signer: Pubkey,
     pub nonce: u8,
 
+    /// SRM or MSRM account of `signer`, defines Serum fee tier.
     pub srm_vault: Pubkey,
+    /// `FeeTier` as `u8`, swaps are rejected while `srm_vault` balance gives lower tier.
+    pub min_fee_tier: u8,
+    /// Only wallets with `AllowlistEntry` can swap.
+    pub allowlist_enabled: bool,
+
+    /// Markets of this program only can be used: serum-dex, OpenBook or any other deploy.
+    pub dex_program: Pubkey,
 }
 
 #[derive(Accounts)]
@@ -61,7 +168,7 @@ pub struct InitializeSaver<'info> {
     pub signer: AccountInfo<'info>,
 
     #[account(
-        constraint = srm_vault.mint == srm_token::ID,
+        constraint = srm_vault.mint == srm_token::ID || srm_vault.mint == msrm_token::ID,
         constraint = srm_vault.owner == *signer.key,
         constraint = srm_vault.amount == 0,
         constraint = srm_vault.delegate.is_none(),
@@ -71,91 +178,255 @@ pub struct InitializeSaver<'info> {
     )]
     pub srm_vault: Box<Account<'info, TokenAccount>>,
 
+    #[account(executable)]
+    pub dex_program: AccountInfo<'info>,
+
+    pub authority: AccountInfo<'info>,
+
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> InitializeSaver<'info> {
-    pub fn handle(&mut self, nonce: u8) -> SaverResult {
+    pub fn handle(&mut self, nonce: u8, min_fee_tier: FeeTier) -> SaverResult {
         let saver = &mut self.saver;
 
+        saver.authority = self.authority.key();
+
         saver.signer = self.signer.key();
         saver.nonce = nonce;
 
+        saver.dex_program = self.dex_program.key();
+
         saver.srm_vault = self.srm_vault.key();
+        saver.min_fee_tier = min_fee_tier as u8;
+
+        Ok(())
+    }
+}
 
+#[derive(Accounts)]
+pub struct SetMinFeeTier<'info> {
+    #[account(mut, has_one = authority)]
+    pub saver: Box<Account<'info, Saver>>,
+    pub authority: Signer<'info>,
+}
+
+impl<'info> SetMinFeeTier<'info> {
+    pub fn handle(&mut self, min_fee_tier: FeeTier) -> SaverResult {
+        self.saver.min_fee_tier = min_fee_tier as u8;
         Ok(())
     }
 }
 
+#[derive(Accounts)]
+pub struct SetAllowlistEnabled<'info> {
+    #[account(mut, has_one = authority)]
+    pub saver: Box<Account<'info, Saver>>,
+    pub authority: Signer<'info>,
+}
+
+impl<'info> SetAllowlistEnabled<'info> {
+    pub fn handle(&mut self, enabled: bool) -> SaverResult {
+        self.saver.allowlist_enabled = enabled;
+        Ok(())
+    }
+}
+
+/// Allows `wallet` to swap through saver with enabled allowlist.
 #[account]
 #[derive(Debug, Default, Copy)]
-pub struct SaverMarket {
+pub struct AllowlistEntry {
     pub saver: Pubkey,
+    pub wallet: Pubkey,
+    pub nonce: u8,
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u8)]
+pub struct AddToAllowlist<'info> {
+    #[account(
+        init,
+        seeds = [b"allowlist".as_ref(), (*saver).as_ref().key.as_ref(), wallet.key.as_ref()],
+        bump = nonce,
+        payer = payer,
+    )]
+    pub allowlist_entry: Box<Account<'info, AllowlistEntry>>,
+
+    #[account(has_one = authority)]
+    pub saver: Box<Account<'info, Saver>>,
+    pub authority: Signer<'info>,
+    pub wallet: AccountInfo<'info>,
+
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AddToAllowlist<'info> {
+    pub fn handle(&mut self, nonce: u8) -> SaverResult {
+        let allowlist_entry = &mut self.allowlist_entry;
+
+        allowlist_entry.saver = self.saver.key();
+        allowlist_entry.wallet = self.wallet.key();
+        allowlist_entry.nonce = nonce;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RemoveFromAllowlist<'info> {
+    #[account(mut, has_one = saver, close = authority)]
+    pub allowlist_entry: Box<Account<'info, AllowlistEntry>>,
 
+    #[account(has_one = authority)]
+    pub saver: Box<Account<'info, Saver>>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+/// Swaps of all users go through one `OpenOrders`. Serum write-locks market, bids, asks and queues
+/// on every order, so swaps through one market are serialized by the DEX anyway and more
+/// `OpenOrders` (or per-`OpenOrders` vaults and counters) would not make them parallel.
+#[account]
+#[derive(Debug, Default, Copy)]
+pub struct SaverMarket {
+    pub saver: Pubkey,
+    pub market: Pubkey,
     pub open_orders: Pubkey,
     pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub coin_decimals: u8,
+    pub pc_decimals: u8,
 
     pub coin_vault: Pubkey,
     pub pc_vault: Pubkey,
+
+    /// Pyth price account of coin in pc, `Pubkey::default()` if not used.
+    pub oracle: Pubkey,
+    /// Max deviation of swap average price from oracle price against the user.
+    pub oracle_band_bps: u64,
+
+    /// Native pc quantity of one swap without fees, zero means no limit.
+    pub max_notional_per_swap: u64,
+    /// Swaps in one slot, zero means no limit.
+    pub max_swaps_per_slot: u64,
+    /// Native pc quantity swapped by one wallet in a day (UTC), tracked in `UserVolume`, zero
+    /// means no limit.
+    pub max_daily_volume_per_user: u64,
+
+    /// Updated only if `max_swaps_per_slot` is set.
+    pub last_swap_slot: u64,
+    pub last_slot_swap_count: u64,
+
+    /// Native coin in `coin_vault` which belongs to the saver, not to users.
+    pub coin_fees: u64,
+    /// Native pc in `pc_vault` which belongs to the saver, not to users.
+    pub pc_fees: u64,
+    /// Native pc paid from `pc_fees` for `Crank` which consumed at least one event.
+    pub crank_tip: u64,
+    /// Max slots since oracle price was published.
+    pub max_oracle_age_slots: u64,
+    /// `Crank` is tipped at most once per slot, so consuming events one by one in separate
+    /// transactions does not drain `pc_fees`.
+    pub next_tip_slot: u64,
 }
 
+impl SaverMarket {
+    pub fn record_swap(&mut self, slot: u64, native_pc_qty: u64) -> SaverResult {
+        if self.max_notional_per_swap > 0 && native_pc_qty > self.max_notional_per_swap {
+            return Err(SaverError::MaxNotionalExceeded.into());
+        }
+
+        // Counter is not touched without the limit, so swaps do not write more than fees.
+        if self.max_swaps_per_slot > 0 {
+            if self.last_swap_slot != slot {
+                self.last_swap_slot = slot;
+                self.last_slot_swap_count = 0;
+            }
+            self.last_slot_swap_count += 1;
+            if self.last_slot_swap_count > self.max_swaps_per_slot {
+                return Err(SaverError::MaxSwapsPerSlotExceeded.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn record_fees(&mut self, native_coin_qty: u64, native_pc_qty: u64) {
+        self.coin_fees = self.coin_fees.saturating_add(native_coin_qty);
+        self.pc_fees = self.pc_fees.saturating_add(native_pc_qty);
+    }
+}
+
+/// Vaults are created as program addresses of `[saver_market, mint]`, so every market has own
+/// vaults even if mint is shared with another market. Mints should be coin and pc mints of the
+/// market, since vaults and decimals are derived from them.
+/// Permissioned market is supported if saver `signer` is its open orders and prune authority, since
+/// serum-dex v0.4 uses prune authority to consume events of permissioned market.
 #[derive(Accounts)]
+#[instruction(coin_vault_nonce: u8, pc_vault_nonce: u8)]
 pub struct InitializeMarket<'info> {
     #[account(init, payer = payer)]
     pub saver_market: Box<Account<'info, SaverMarket>>,
 
-    #[account(has_one = signer)]
+    #[account(has_one = signer, has_one = dex_program)]
     pub saver: Box<Account<'info, Saver>>,
     pub signer: AccountInfo<'info>,
 
     pub coin_mint: Box<Account<'info, TokenMint>>,
     #[account(
-        constraint = coin_vault.mint == coin_mint.key(),
-        constraint = coin_vault.owner == saver.signer,
-        constraint = coin_vault.amount == 0,
-        constraint = coin_vault.delegate.is_none(),
-        constraint = coin_vault.state == TokenAccountState::Initialized,
-        constraint = coin_vault.close_authority.is_none(),
-        constraint = gata(&saver.signer, &coin_vault.mint) == coin_vault.key(),
+        mut,
+        seeds = [(*saver_market).as_ref().key.as_ref(), (*coin_mint).as_ref().key.as_ref()],
+        bump = coin_vault_nonce,
     )]
-    pub coin_vault: Box<Account<'info, TokenAccount>>,
+    pub coin_vault: AccountInfo<'info>,
 
     pub pc_mint: Box<Account<'info, TokenMint>>,
     #[account(
-        constraint = pc_vault.mint == pc_mint.key(),
-        constraint = pc_vault.owner == saver.signer,
-        // constraint = pc_vault.amount == 0,
-        constraint = pc_vault.delegate.is_none(),
-        constraint = pc_vault.state == TokenAccountState::Initialized,
-        constraint = pc_vault.close_authority.is_none(),
-        constraint = gata(&saver.signer, &pc_vault.mint) == pc_vault.key(),
+        mut,
+        seeds = [(*saver_market).as_ref().key.as_ref(), (*pc_mint).as_ref().key.as_ref()],
+        bump = pc_vault_nonce,
     )]
-    pub pc_vault: Box<Account<'info, TokenAccount>>,
+    pub pc_vault: AccountInfo<'info>,
 
-    pub dex_program: Program<'info, Dex>,
+    pub dex_program: AccountInfo<'info>,
     pub dex_market: AccountInfo<'info>,
     #[account(mut)]
     pub dex_open_orders: AccountInfo<'info>,
 
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
+    pub spl_token_program: Program<'info, SplToken>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 impl<'info> InitializeMarket<'info> {
-    pub fn handle(&mut self) -> SaverResult {
+    pub fn handle(&mut self, coin_vault_nonce: u8, pc_vault_nonce: u8) -> SaverResult {
         self.initialize()?;
+        self.init_vaults(coin_vault_nonce, pc_vault_nonce)?;
         self.init_open_orders()?;
         Ok(())
     }
 
     pub fn initialize(&mut self) -> SaverResult {
+        let (coin_mint, pc_mint) = get_market_mints(self.dex_program.key, &self.dex_market)?;
+        if coin_mint != self.coin_mint.key() || pc_mint != self.pc_mint.key() {
+            return Err(SaverError::InvalidMarketMint.into());
+        }
+
         let saver_market = &mut self.saver_market;
 
         saver_market.saver = self.saver.key();
 
+        saver_market.market = self.dex_market.key();
         saver_market.open_orders = self.dex_open_orders.key();
-        saver_market.coin_lot_size = get_market_coin_lot_size(&self.dex_market)?;
+        let (coin_lot_size, pc_lot_size) =
+            get_market_lot_sizes(self.dex_program.key, &self.dex_market)?;
+        saver_market.coin_lot_size = coin_lot_size;
+        saver_market.pc_lot_size = pc_lot_size;
+        saver_market.coin_decimals = self.coin_mint.decimals;
+        saver_market.pc_decimals = self.pc_mint.decimals;
 
         saver_market.coin_vault = self.coin_vault.key();
         saver_market.pc_vault = self.pc_vault.key();
@@ -163,22 +434,243 @@ impl<'info> InitializeMarket<'info> {
         Ok(())
     }
 
+    fn init_vaults(&self, coin_vault_nonce: u8, pc_vault_nonce: u8) -> ProgramResult {
+        let vaults = [
+            (&self.coin_vault, &self.coin_mint, coin_vault_nonce),
+            (&self.pc_vault, &self.pc_mint, pc_vault_nonce),
+        ];
+        for (vault, mint, nonce) in vaults {
+            token::create_account(
+                self.payer.to_account_info(),
+                vault.clone(),
+                mint.to_account_info(),
+                self.signer.clone(),
+                &self.rent,
+                &[&[
+                    (*self.saver_market).as_ref().key.as_ref(),
+                    (**mint).as_ref().key.as_ref(),
+                    &[nonce],
+                ]],
+            )?;
+        }
+        Ok(())
+    }
+
     fn init_open_orders(&self) -> ProgramResult {
         dex::init_open_orders(
             self.dex_program.to_account_info(),
             self.dex_open_orders.clone(),
             self.signer.clone(),
             self.dex_market.clone(),
+            self.rent.to_account_info(),
             &[&[(*self.saver).as_ref().key.as_ref(), &[self.saver.nonce]]],
         )
     }
 }
 
+#[derive(Accounts)]
+pub struct SetMarketLimits<'info> {
+    #[account(mut, has_one = saver)]
+    pub saver_market: Box<Account<'info, SaverMarket>>,
+
+    #[account(has_one = authority)]
+    pub saver: Box<Account<'info, Saver>>,
+    pub authority: Signer<'info>,
+}
+
+impl<'info> SetMarketLimits<'info> {
+    pub fn handle(
+        &mut self,
+        max_notional_per_swap: u64,
+        max_swaps_per_slot: u64,
+        max_daily_volume_per_user: u64,
+    ) -> SaverResult {
+        let saver_market = &mut self.saver_market;
+
+        saver_market.max_notional_per_swap = max_notional_per_swap;
+        saver_market.max_swaps_per_slot = max_swaps_per_slot;
+        saver_market.max_daily_volume_per_user = max_daily_volume_per_user;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetMarketOracle<'info> {
+    #[account(mut, has_one = saver)]
+    pub saver_market: Box<Account<'info, SaverMarket>>,
+
+    #[account(has_one = authority)]
+    pub saver: Box<Account<'info, Saver>>,
+    pub authority: Signer<'info>,
+}
+
+impl<'info> SetMarketOracle<'info> {
+    pub fn handle(
+        &mut self,
+        oracle: Pubkey,
+        oracle_band_bps: u64,
+        max_oracle_age_slots: u64,
+    ) -> SaverResult {
+        let saver_market = &mut self.saver_market;
+
+        saver_market.oracle = oracle;
+        saver_market.oracle_band_bps = oracle_band_bps;
+        saver_market.max_oracle_age_slots = max_oracle_age_slots;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetCrankTip<'info> {
+    #[account(mut, has_one = saver)]
+    pub saver_market: Box<Account<'info, SaverMarket>>,
+
+    #[account(has_one = authority)]
+    pub saver: Box<Account<'info, Saver>>,
+    pub authority: Signer<'info>,
+}
+
+impl<'info> SetCrankTip<'info> {
+    pub fn handle(&mut self, crank_tip: u64) -> SaverResult {
+        self.saver_market.crank_tip = crank_tip;
+        Ok(())
+    }
+}
+
+/// Swap statistics of `wallet` on `Saver`. `wallet` is owner of token accounts paying for swaps,
+/// not `wallet_signer` of `Swap`, so swaps by a delegate are recorded to the owner.
+#[account]
+#[derive(Debug, Default, Copy)]
+pub struct UserStats {
+    pub saver: Pubkey,
+    pub wallet: Pubkey,
+    pub nonce: u8,
+
+    pub swap_count: u64,
+    pub coin_volume: u64,
+    pub pc_volume: u64,
+    pub pc_fee_saved: u64,
+}
+
+impl UserStats {
+    pub fn record_swap(&mut self, coin_qty: u64, pc_qty: u64, pc_fee_saved: u64) {
+        self.swap_count = self.swap_count.saturating_add(1);
+        self.coin_volume = self.coin_volume.saturating_add(coin_qty);
+        self.pc_volume = self.pc_volume.saturating_add(pc_qty);
+        self.pc_fee_saved = self.pc_fee_saved.saturating_add(pc_fee_saved);
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u8)]
+pub struct InitializeUserStats<'info> {
+    #[account(
+        init,
+        seeds = [b"stats".as_ref(), (*saver).as_ref().key.as_ref(), wallet.key.as_ref()],
+        bump = nonce,
+        payer = payer,
+    )]
+    pub user_stats: Box<Account<'info, UserStats>>,
+
+    pub saver: Box<Account<'info, Saver>>,
+    pub wallet: AccountInfo<'info>,
+
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeUserStats<'info> {
+    pub fn handle(&mut self, nonce: u8) -> SaverResult {
+        let user_stats = &mut self.user_stats;
+
+        user_stats.saver = self.saver.key();
+        user_stats.wallet = self.wallet.key();
+        user_stats.nonce = nonce;
+
+        Ok(())
+    }
+}
+
+/// Daily volume of wallet on `SaverMarket`, used for `SaverMarket::max_daily_volume_per_user`.
+#[account]
+#[derive(Debug, Default, Copy)]
+pub struct UserVolume {
+    pub saver_market: Pubkey,
+    pub wallet: Pubkey,
+    pub nonce: u8,
+
+    /// Days since unix epoch.
+    pub day: i64,
+    pub pc_volume: u64,
+}
+
+impl UserVolume {
+    pub fn record_swap(&mut self, day: i64, native_pc_qty: u64, max_volume: u64) -> SaverResult {
+        if self.day != day {
+            self.day = day;
+            self.pc_volume = 0;
+        }
+        self.pc_volume = self.pc_volume.saturating_add(native_pc_qty);
+        if self.pc_volume > max_volume {
+            return Err(SaverError::MaxDailyVolumeExceeded.into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(nonce: u8)]
+pub struct InitializeUserVolume<'info> {
+    #[account(
+        init,
+        seeds = [b"volume".as_ref(), (*saver_market).as_ref().key.as_ref(), wallet.key.as_ref()],
+        bump = nonce,
+        payer = payer,
+    )]
+    pub user_volume: Box<Account<'info, UserVolume>>,
+
+    pub saver_market: Box<Account<'info, SaverMarket>>,
+    pub wallet: AccountInfo<'info>,
+
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeUserVolume<'info> {
+    pub fn handle(&mut self, nonce: u8) -> SaverResult {
+        let user_volume = &mut self.user_volume;
+
+        user_volume.saver_market = self.saver_market.key();
+        user_volume.wallet = self.wallet.key();
+        user_volume.nonce = nonce;
+
+        Ok(())
+    }
+}
+
+/// Order is immediate-or-cancel matched against at most `limit` orders. Non-zero
+/// `max_price_impact_bps` moves `limit_price` closer to the best price of the book, so the order
+/// stops instead of walking thin book.
+/// Users of `SaverMarket` share one `OpenOrders` and serum detects self-trade by `OpenOrders`
+/// address. Only `Swap` places orders from it and these orders never rest on the book, so one
+/// user can not be matched with another saver user; `self_trade_behavior` and
+/// `client_order_id` are passed to serum as is (client id is visible in fill events).
+/// `wallet_signer` is owner of the paying wallet, or its delegate approved by the owner (e.g. a
+/// bot key); in that case both wallets should belong to the same owner.
+/// Free funds of the saver `OpenOrders` are settled to market fees before the order; both settles
+/// pay referrer rebates to `pc_vault` the same way as `Settle`.
+/// Remaining accounts: optional `UserStats` of wallet owner, `AllowlistEntry` of wallet owner
+/// required if allowlist is enabled on `Saver` and `UserVolume` of wallet owner required if
+/// `SaverMarket` limits daily volume, recognized by type; and oracle of `SaverMarket` if it is
+/// set. Order is not important.
 #[derive(Accounts)]
 pub struct Swap<'info> {
     #[account(
         has_one = signer,
         has_one = srm_vault,
+        has_one = dex_program,
     )]
     pub saver: Box<Account<'info, Saver>>,
     pub signer: AccountInfo<'info>,
@@ -188,7 +680,9 @@ pub struct Swap<'info> {
     pub srm_vault: Box<Account<'info, TokenAccount>>,
 
     #[account(
+        mut,
         has_one = saver,
+        has_one = market,
         has_one = open_orders,
         has_one = coin_vault,
         has_one = pc_vault,
@@ -201,9 +695,9 @@ pub struct Swap<'info> {
     pub pc_vault: Box<Account<'info, TokenAccount>>,
 
     #[account(mut)]
-    pub coin_wallet: AccountInfo<'info>,
+    pub coin_wallet: Box<Account<'info, TokenAccount>>,
     #[account(mut)]
-    pub pc_wallet: AccountInfo<'info>,
+    pub pc_wallet: Box<Account<'info, TokenAccount>>,
     pub wallet_signer: Signer<'info>,
 
     #[account(mut)]
@@ -224,53 +718,128 @@ pub struct Swap<'info> {
     pub dex_pc_vault: AccountInfo<'info>,
     pub dex_vault_signer: AccountInfo<'info>,
 
-    pub dex_program: Program<'info, Dex>,
+    pub dex_program: AccountInfo<'info>,
     pub spl_token_program: Program<'info, SplToken>,
 }
 
 impl<'info> Swap<'info> {
+    #[allow(clippy::too_many_arguments)]
     pub fn handle(
         &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
         side: Side,
         limit_price: u64,
         max_coin_qty: u64,
         max_native_pc_qty_including_fees: u64,
+        limit: u16,
+        max_price_impact_bps: u64,
+        self_trade_behavior: SelfTradeBehavior,
+        client_order_id: u64,
     ) -> SaverResult {
-        let coin_balance = self.coin_vault.amount;
-        let pc_balance = self.pc_vault.amount;
+        // Saver without enough SRM is worse than trading directly.
+        let fee_rates = get_fee_rates(self.market.key, &self.srm_vault);
+        if (fee_rates.fee_tier as u8) < self.saver.min_fee_tier {
+            return Err(SaverError::FeeTierTooLow.into());
+        }
 
-        let (take_from, take_to, take_amount) = match side {
-            Side::Bid => (
-                self.pc_wallet.clone(),
-                self.pc_vault.to_account_info(),
-                max_native_pc_qty_including_fees,
-            ),
+        let user = self.get_user(side)?;
+        if self.saver.allowlist_enabled {
+            self.check_allowlist(remaining_accounts, &user)?;
+        }
+
+        // Match order against the book first, so we take from the user only what will be spent
+        // instead of the full max.
+        let max_pc_qty = match side {
+            Side::Bid => {
+                fee_rates.remove_taker_fee(max_native_pc_qty_including_fees)
+                    / self.saver_market.pc_lot_size
+            }
+            Side::Ask => u64::MAX,
+        };
+        let orders = load_crossed_orders(
+            self.dex_program.key,
+            &self.market,
+            &self.bids,
+            &self.asks,
+            side,
+            limit_price,
+            limit,
+        )?;
+        let best_price = orders.first().ok_or(SaverError::NoLiquidity)?.price;
+        let limit_price = clamp_limit_price(side, limit_price, best_price, max_price_impact_bps);
+        let fill = simulate_fill(&orders, side, limit_price, max_coin_qty, max_pc_qty, limit);
+        if fill.coin_qty == 0 {
+            return Err(SaverError::NoLiquidity.into());
+        }
+
+        let native_pc_qty = fill
+            .pc_qty
+            .checked_mul(self.saver_market.pc_lot_size)
+            .ok_or(SaverError::PcQtyOverflow)?;
+        self.check_limits(remaining_accounts, &user, native_pc_qty)?;
+        if self.saver_market.oracle != Pubkey::default() {
+            self.check_oracle(remaining_accounts, side, fill.coin_qty, native_pc_qty)?;
+        }
+
+        let (take_from, take_to, take_amount, max_native_pc_qty_including_fees) = match side {
+            Side::Bid => {
+                let take_amount = native_pc_qty
+                    .checked_add(fee_rates.taker_fee(native_pc_qty))
+                    .ok_or(SaverError::PcQtyOverflow)?;
+                (
+                    &self.pc_wallet,
+                    self.pc_vault.to_account_info(),
+                    take_amount,
+                    take_amount,
+                )
+            }
             Side::Ask => (
-                self.coin_wallet.clone(),
+                &self.coin_wallet,
                 self.coin_vault.to_account_info(),
-                max_coin_qty
+                fill.coin_qty
                     .checked_mul(self.saver_market.coin_lot_size)
                     .ok_or(SaverError::CoinQtyOverflow)?,
+                max_native_pc_qty_including_fees,
             ),
         };
 
-        token::transfer(
+        let seeds: &[&[&[u8]]] = &[&[(*self.saver).as_ref().key.as_ref(), &[self.saver.nonce]]];
+
+        // Free funds already in shared OpenOrders (maker fills, referrer rebates, leftovers) are
+        // not result of this swap, so they are settled to market fees before the order.
+        let coin_balance = self.coin_vault.amount;
+        let pc_balance = self.pc_vault.amount;
+        self.settle_funds(seeds)?;
+        self.coin_vault.reload()?;
+        self.pc_vault.reload()?;
+        self.saver_market.record_fees(
+            self.coin_vault
+                .amount
+                .checked_sub(coin_balance)
+                .ok_or(SaverError::CoinBalanceChangeUnderflow)?,
+            self.pc_vault
+                .amount
+                .checked_sub(pc_balance)
+                .ok_or(SaverError::PcBalanceChangeUnderflow)?,
+        );
+
+        let coin_balance = self.coin_vault.amount;
+        let pc_balance = self.pc_vault.amount;
+
+        token::transfer_from(
             take_from,
             take_to,
             self.wallet_signer.to_account_info(),
             take_amount,
-            &[],
         )?;
 
-        let seeds: &[&[&[u8]]] = &[&[(*self.saver).as_ref().key.as_ref(), &[self.saver.nonce]]];
-
         let order_payer = match side {
             Side::Bid => self.pc_vault.to_account_info(),
             Side::Ask => self.coin_vault.to_account_info(),
         };
 
         invoke_signed(
-            &serum_dex::instruction::new_order(
+            &dex::adapter::new_order(
                 self.market.key,
                 self.open_orders.key,
                 self.request_queue.key,
@@ -287,11 +856,11 @@ impl<'info> Swap<'info> {
                 self.dex_program.key,
                 side,
                 NonZeroU64::new(limit_price).ok_or(SaverError::NonZeroU64)?,
-                NonZeroU64::new(max_coin_qty).ok_or(SaverError::NonZeroU64)?,
+                NonZeroU64::new(fill.coin_qty).ok_or(SaverError::NonZeroU64)?,
                 serum_dex::matching::OrderType::ImmediateOrCancel,
-                0, // client_order_id
-                serum_dex::instruction::SelfTradeBehavior::AbortTransaction,
-                u16::MAX, // limit
+                client_order_id,
+                self_trade_behavior,
+                limit,
                 NonZeroU64::new(max_native_pc_qty_including_fees).ok_or(SaverError::NonZeroU64)?,
             )
             .map_err(|e| -> ProgramError { e.into() })?,
@@ -313,42 +882,18 @@ impl<'info> Swap<'info> {
             seeds,
         )?;
 
-        invoke_signed(
-            &serum_dex::instruction::settle_funds(
-                self.dex_program.key,
-                self.market.key,
-                self.spl_token_program.key,
-                self.open_orders.key,
-                self.signer.key,
-                self.dex_coin_vault.key,
-                (*self.coin_vault).as_ref().key,
-                self.dex_pc_vault.key,
-                (*self.pc_vault).as_ref().key,
-                Some(self.dex_pc_vault.key),
-                self.dex_vault_signer.key,
-            )
-            .map_err(|e| -> ProgramError { e.into() })?,
-            &[
-                self.market.clone(),
-                self.open_orders.clone(),
-                self.signer.clone(),
-                self.dex_coin_vault.clone(),
-                self.dex_pc_vault.clone(),
-                self.coin_vault.to_account_info(),
-                self.pc_vault.to_account_info(),
-                self.dex_vault_signer.clone(),
-                self.spl_token_program.to_account_info(),
-                self.dex_pc_vault.to_account_info(),
-            ],
-            seeds,
-        )?;
+        self.settle_funds(seeds)?;
 
         self.coin_vault.reload()?;
-        let coin_balance_change = self.coin_vault.amount - coin_balance;
+        let coin_balance_change = self
+            .coin_vault
+            .amount
+            .checked_sub(coin_balance)
+            .ok_or(SaverError::CoinBalanceChangeUnderflow)?;
         if coin_balance_change > 0 {
             token::transfer(
                 self.coin_vault.to_account_info(),
-                self.coin_wallet.clone(),
+                self.coin_wallet.to_account_info(),
                 self.signer.clone(),
                 coin_balance_change,
                 seeds,
@@ -356,17 +901,417 @@ impl<'info> Swap<'info> {
         }
 
         self.pc_vault.reload()?;
-        let pc_balance_change = self.pc_vault.amount - pc_balance;
+        let pc_balance_change = self
+            .pc_vault
+            .amount
+            .checked_sub(pc_balance)
+            .ok_or(SaverError::PcBalanceChangeUnderflow)?;
         if pc_balance_change > 0 {
             token::transfer(
                 self.pc_vault.to_account_info(),
-                self.pc_wallet.clone(),
+                self.pc_wallet.to_account_info(),
                 self.signer.clone(),
                 pc_balance_change,
                 seeds,
             )?;
         }
 
+        // Vaults are shared between users, so everything above balance before swap should be
+        // transferred back to the user and nothing should be taken from other users.
+        self.coin_vault.reload()?;
+        self.pc_vault.reload()?;
+        if self.coin_vault.amount != coin_balance || self.pc_vault.amount != pc_balance {
+            return Err(SaverError::VaultBalanceMismatch.into());
+        }
+
+        let user_stats = remaining_accounts
+            .iter()
+            .find_map(|info| Account::<UserStats>::try_from(info).ok());
+        if let Some(user_stats) = user_stats {
+            let (coin_qty, pc_qty) = match side {
+                Side::Bid => (
+                    coin_balance_change,
+                    take_amount.saturating_sub(pc_balance_change),
+                ),
+                Side::Ask => (
+                    take_amount.saturating_sub(coin_balance_change),
+                    pc_balance_change,
+                ),
+            };
+            let pc_fee_saved = get_taker_fee_saved(fee_rates.fee_tier, native_pc_qty);
+            self.update_user_stats(user_stats, &user, coin_qty, pc_qty, pc_fee_saved)?;
+        }
+
+        Ok(())
+    }
+
+    fn settle_funds(&self, seeds: &[&[&[u8]]]) -> SaverResult {
+        dex::settle_funds(
+            self.dex_program.clone(),
+            self.market.clone(),
+            self.open_orders.clone(),
+            self.signer.clone(),
+            self.dex_coin_vault.clone(),
+            self.dex_pc_vault.clone(),
+            self.coin_vault.to_account_info(),
+            self.pc_vault.to_account_info(),
+            self.dex_vault_signer.clone(),
+            self.spl_token_program.to_account_info(),
+            seeds,
+        )
+    }
+
+    /// Owner of the paying wallet. Delegated swaps can not send output to somebody else.
+    fn get_user(&self, side: Side) -> SaverResult<Pubkey> {
+        let user = match side {
+            Side::Bid => self.pc_wallet.owner,
+            Side::Ask => self.coin_wallet.owner,
+        };
+        if user != self.wallet_signer.key()
+            && (self.coin_wallet.owner != user || self.pc_wallet.owner != user)
+        {
+            return Err(SaverError::InvalidWalletOwner.into());
+        }
+        Ok(user)
+    }
+
+    fn check_limits(
+        &mut self,
+        remaining_accounts: &[AccountInfo<'info>],
+        user: &Pubkey,
+        native_pc_qty: u64,
+    ) -> SaverResult {
+        let clock = Clock::get()?;
+        self.saver_market.record_swap(clock.slot, native_pc_qty)?;
+
+        let max_volume = self.saver_market.max_daily_volume_per_user;
+        if max_volume > 0 {
+            let mut user_volume = remaining_accounts
+                .iter()
+                .filter_map(|info| Account::<UserVolume>::try_from(info).ok())
+                .find(|user_volume| {
+                    user_volume.saver_market == self.saver_market.key()
+                        && user_volume.wallet == *user
+                })
+                .ok_or(SaverError::UserVolumeRequired)?;
+            user_volume.record_swap(clock.unix_timestamp / 86_400, native_pc_qty, max_volume)?;
+            user_volume.exit(&ID)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_oracle(
+        &self,
+        remaining_accounts: &[AccountInfo<'info>],
+        side: Side,
+        coin_qty: u64,
+        native_pc_qty: u64,
+    ) -> SaverResult {
+        let oracle_info = remaining_accounts
+            .iter()
+            .find(|info| *info.key == self.saver_market.oracle)
+            .ok_or(SaverError::OracleRequired)?;
+        let oracle_price = oracle::load_price(
+            &oracle_info.try_borrow_data()?,
+            Clock::get()?.slot,
+            self.saver_market.max_oracle_age_slots,
+        )?;
+        let native_coin_qty = coin_qty
+            .checked_mul(self.saver_market.coin_lot_size)
+            .ok_or(SaverError::CoinQtyOverflow)?;
+        oracle::check_price_band(
+            &oracle_price,
+            side,
+            native_coin_qty,
+            native_pc_qty,
+            self.saver_market.coin_decimals,
+            self.saver_market.pc_decimals,
+            self.saver_market.oracle_band_bps,
+        )
+    }
+
+    /// Any `AllowlistEntry` of saver and user is valid, since only authority can create them.
+    fn check_allowlist(
+        &self,
+        remaining_accounts: &[AccountInfo<'info>],
+        user: &Pubkey,
+    ) -> SaverResult {
+        let allowed = remaining_accounts
+            .iter()
+            .filter_map(|info| Account::<AllowlistEntry>::try_from(info).ok())
+            .any(|entry| entry.saver == self.saver.key() && entry.wallet == *user);
+        if !allowed {
+            return Err(SaverError::WalletNotAllowed.into());
+        }
+        Ok(())
+    }
+
+    fn update_user_stats(
+        &self,
+        mut user_stats: Account<'info, UserStats>,
+        user: &Pubkey,
+        coin_qty: u64,
+        pc_qty: u64,
+        pc_fee_saved: u64,
+    ) -> SaverResult {
+        if user_stats.saver != self.saver.key() || user_stats.wallet != *user {
+            return Err(SaverError::InvalidUserStats.into());
+        }
+
+        user_stats.record_swap(coin_qty, pc_qty, pc_fee_saved);
+        user_stats.exit(&ID)
+    }
+}
+
+/// Permissionless `consume_events` for saver `OpenOrders`, so referrer rebates of its taker events
+/// become settleable. Serum stops at the first event of `OpenOrders` which is not passed, so crank
+/// makes progress only while the head of the queue belongs to the saver. On permissioned market
+/// saver `signer` consumes events as prune authority of the market.
+/// `tip_wallet` (pc mint) gets `crank_tip` (while `pc_fees` is enough) if at least one event was
+/// consumed and no crank was tipped in this slot.
+#[derive(Accounts)]
+pub struct Crank<'info> {
+    #[account(has_one = signer, has_one = dex_program)]
+    pub saver: Box<Account<'info, Saver>>,
+    pub signer: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        has_one = saver,
+        has_one = market,
+        has_one = open_orders,
+        has_one = pc_vault,
+    )]
+    pub saver_market: Box<Account<'info, SaverMarket>>,
+
+    #[account(mut)]
+    pub pc_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub tip_wallet: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub market: AccountInfo<'info>,
+    #[account(mut)]
+    pub open_orders: AccountInfo<'info>,
+    #[account(mut)]
+    pub event_queue: AccountInfo<'info>,
+
+    pub dex_program: AccountInfo<'info>,
+    pub spl_token_program: Program<'info, SplToken>,
+}
+
+impl<'info> Crank<'info> {
+    pub fn handle(&mut self, limit: u16) -> SaverResult {
+        if self.tip_wallet.mint != self.pc_vault.mint {
+            return Err(SaverError::InvalidTipWallet.into());
+        }
+
+        let permissioned =
+            dex::check_market_authorities(self.dex_program.key, &self.market, self.signer.key)?;
+
+        let event_count = get_event_queue_count(&self.event_queue)?;
+
+        let seeds: &[&[&[u8]]] = &[&[(*self.saver).as_ref().key.as_ref(), &[self.saver.nonce]]];
+        let open_orders = vec![self.open_orders.key];
+        let mut accounts = vec![
+            self.open_orders.clone(),
+            self.market.clone(),
+            self.event_queue.clone(),
+            self.dex_program.clone(),
+        ];
+        if permissioned {
+            accounts.push(self.signer.clone());
+            invoke_signed(
+                &dex::consume_events_permissioned(
+                    self.dex_program.key,
+                    &open_orders,
+                    self.market.key,
+                    self.event_queue.key,
+                    self.signer.key,
+                    limit,
+                ),
+                &accounts,
+                seeds,
+            )?;
+        } else {
+            accounts.push(self.pc_vault.to_account_info());
+            invoke(
+                &dex::adapter::consume_events(
+                    self.dex_program.key,
+                    open_orders,
+                    self.market.key,
+                    self.event_queue.key,
+                    (*self.pc_vault).as_ref().key, // Not used
+                    (*self.pc_vault).as_ref().key, // Not used
+                    limit,
+                )
+                .map_err(|e| -> ProgramError { e.into() })?,
+                &accounts,
+            )?;
+        }
+
+        let slot = Clock::get()?.slot;
+        if get_event_queue_count(&self.event_queue)? >= event_count
+            || slot < self.saver_market.next_tip_slot
+        {
+            return Ok(());
+        }
+
+        let tip = self.saver_market.crank_tip.min(self.saver_market.pc_fees);
+        if tip > 0 {
+            token::transfer(
+                self.pc_vault.to_account_info(),
+                self.tip_wallet.to_account_info(),
+                self.signer.clone(),
+                tip,
+                seeds,
+            )?;
+            self.saver_market.pc_fees -= tip;
+            self.saver_market.next_tip_slot = slot + 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Permissionless `settle_funds` of saver `OpenOrders` without a trade. Everything settled
+/// (maker fills, referrer rebates, leftovers) belongs to the saver and is added to market fees.
+/// Authorities of permissioned market are not checked, so funds of the saver can be settled even
+/// after the market authority is rotated away from saver `signer`.
+#[derive(Accounts)]
+pub struct Settle<'info> {
+    #[account(has_one = signer, has_one = dex_program)]
+    pub saver: Box<Account<'info, Saver>>,
+    pub signer: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        has_one = saver,
+        has_one = market,
+        has_one = open_orders,
+        has_one = coin_vault,
+        has_one = pc_vault,
+    )]
+    pub saver_market: Box<Account<'info, SaverMarket>>,
+
+    #[account(mut)]
+    pub coin_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub pc_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub market: AccountInfo<'info>,
+    #[account(mut)]
+    pub open_orders: AccountInfo<'info>,
+    #[account(mut)]
+    pub dex_coin_vault: AccountInfo<'info>,
+    #[account(mut)]
+    pub dex_pc_vault: AccountInfo<'info>,
+    pub dex_vault_signer: AccountInfo<'info>,
+
+    pub dex_program: AccountInfo<'info>,
+    pub spl_token_program: Program<'info, SplToken>,
+}
+
+impl<'info> Settle<'info> {
+    pub fn handle(&mut self) -> SaverResult {
+        let coin_balance = self.coin_vault.amount;
+        let pc_balance = self.pc_vault.amount;
+
+        let seeds: &[&[&[u8]]] = &[&[(*self.saver).as_ref().key.as_ref(), &[self.saver.nonce]]];
+        dex::settle_funds(
+            self.dex_program.clone(),
+            self.market.clone(),
+            self.open_orders.clone(),
+            self.signer.clone(),
+            self.dex_coin_vault.clone(),
+            self.dex_pc_vault.clone(),
+            self.coin_vault.to_account_info(),
+            self.pc_vault.to_account_info(),
+            self.dex_vault_signer.clone(),
+            self.spl_token_program.to_account_info(),
+            seeds,
+        )?;
+
+        self.coin_vault.reload()?;
+        self.pc_vault.reload()?;
+        let coin_balance_change = self
+            .coin_vault
+            .amount
+            .checked_sub(coin_balance)
+            .ok_or(SaverError::CoinBalanceChangeUnderflow)?;
+        let pc_balance_change = self
+            .pc_vault
+            .amount
+            .checked_sub(pc_balance)
+            .ok_or(SaverError::PcBalanceChangeUnderflow)?;
+
+        self.saver_market
+            .record_fees(coin_balance_change, pc_balance_change);
+
+        Ok(())
+    }
+}
+
+/// Transfer collected market fees to any wallets. Amounts are bounded by `coin_fees` and
+/// `pc_fees`, so balances of users in shared vaults are never touched.
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(has_one = authority, has_one = signer)]
+    pub saver: Box<Account<'info, Saver>>,
+    pub signer: AccountInfo<'info>,
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = saver,
+        has_one = coin_vault,
+        has_one = pc_vault,
+    )]
+    pub saver_market: Box<Account<'info, SaverMarket>>,
+
+    #[account(mut)]
+    pub coin_vault: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub pc_vault: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub coin_wallet: Box<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub pc_wallet: Box<Account<'info, TokenAccount>>,
+
+    pub spl_token_program: Program<'info, SplToken>,
+}
+
+impl<'info> WithdrawFees<'info> {
+    pub fn handle(&mut self, coin_amount: u64, pc_amount: u64) -> SaverResult {
+        if coin_amount > self.saver_market.coin_fees || pc_amount > self.saver_market.pc_fees {
+            return Err(SaverError::WithdrawExceedsFees.into());
+        }
+
+        let seeds: &[&[&[u8]]] = &[&[(*self.saver).as_ref().key.as_ref(), &[self.saver.nonce]]];
+        if coin_amount > 0 {
+            token::transfer(
+                self.coin_vault.to_account_info(),
+                self.coin_wallet.to_account_info(),
+                self.signer.clone(),
+                coin_amount,
+                seeds,
+            )?;
+            self.saver_market.coin_fees -= coin_amount;
+        }
+        if pc_amount > 0 {
+            token::transfer(
+                self.pc_vault.to_account_info(),
+                self.pc_wallet.to_account_info(),
+                self.signer.clone(),
+                pc_amount,
+                seeds,
+            )?;
+            self.saver_market.pc_fees -= pc_amount;
+        }
+
         Ok(())
     }
 }
@@ -0,0 +1,129 @@
+//! Minimal reader of Pyth price account, only aggregate price is used.
+//! https://github.com/pyth-network/pyth-client/blob/v2.2/program/src/oracle/oracle.h
+
+use anchor_lang::prelude::*;
+use serum_dex::matching::Side;
+use std::convert::TryInto;
+
+use crate::error::{SaverError, SaverResult};
+
+const MAGIC: u32 = 0xa1b2_c3d4;
+const VERSION: u32 = 2;
+const ACCOUNT_TYPE_PRICE: u32 = 3;
+const PRICE_STATUS_TRADING: u32 = 1;
+
+const OFFSET_MAGIC: usize = 0;
+const OFFSET_VERSION: usize = 4;
+const OFFSET_ACCOUNT_TYPE: usize = 8;
+const OFFSET_EXPO: usize = 20;
+const OFFSET_AGG_PRICE: usize = 208;
+const OFFSET_AGG_CONF: usize = 216;
+const OFFSET_AGG_STATUS: usize = 224;
+const OFFSET_AGG_PUB_SLOT: usize = 232;
+
+/// Aggregate price, `price * 10^expo` of one coin in pc, and confidence interval `conf` in the
+/// same units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+}
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> SaverResult<[u8; N]> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| SaverError::InvalidOracle.into())
+}
+
+/// Load aggregate price, fails if price account is not trading or aggregate price was published
+/// more than `max_age_slots` before `slot`. Status stays trading when publishers stop, so age
+/// should be checked too.
+pub fn load_price(data: &[u8], slot: u64, max_age_slots: u64) -> SaverResult<OraclePrice> {
+    let magic = u32::from_le_bytes(read(data, OFFSET_MAGIC)?);
+    let version = u32::from_le_bytes(read(data, OFFSET_VERSION)?);
+    let account_type = u32::from_le_bytes(read(data, OFFSET_ACCOUNT_TYPE)?);
+    if magic != MAGIC || version != VERSION || account_type != ACCOUNT_TYPE_PRICE {
+        return Err(SaverError::InvalidOracle.into());
+    }
+
+    let status = u32::from_le_bytes(read(data, OFFSET_AGG_STATUS)?);
+    let price = i64::from_le_bytes(read(data, OFFSET_AGG_PRICE)?);
+    if status != PRICE_STATUS_TRADING || price <= 0 {
+        return Err(SaverError::OraclePriceUnavailable.into());
+    }
+    let pub_slot = u64::from_le_bytes(read(data, OFFSET_AGG_PUB_SLOT)?);
+    if slot.saturating_sub(pub_slot) > max_age_slots {
+        return Err(SaverError::OraclePriceStale.into());
+    }
+
+    Ok(OraclePrice {
+        price,
+        conf: u64::from_le_bytes(read(data, OFFSET_AGG_CONF)?),
+        expo: i32::from_le_bytes(read(data, OFFSET_EXPO)?),
+    })
+}
+
+/// Fails if average price of the fill is worse for taker on `side` than oracle price by more
+/// than `band_bps`, or if oracle is less certain than the band itself (confidence interval is
+/// wider than `band_bps` of price). Quantities are native, decimals are used for conversion to
+/// oracle units.
+#[allow(clippy::too_many_arguments)]
+pub fn check_price_band(
+    oracle_price: &OraclePrice,
+    side: Side,
+    native_coin_qty: u64,
+    native_pc_qty: u64,
+    coin_decimals: u8,
+    pc_decimals: u8,
+    band_bps: u64,
+) -> SaverResult {
+    if oracle_price.conf as u128 * 10_000 > oracle_price.price as u128 * band_bps as u128 {
+        return Err(SaverError::OracleConfidenceTooWide.into());
+    }
+
+    let band_bps = match side {
+        Side::Bid => 10_000 + band_bps as u128,
+        Side::Ask => 10_000u128.saturating_sub(band_bps as u128),
+    };
+    let (fill_expo, oracle_expo) = match oracle_price.expo {
+        expo if expo < 0 => (expo.unsigned_abs(), 0),
+        expo => (0, expo as u32),
+    };
+
+    // fill price / oracle price =
+    //     (native_pc_qty * 10^coin_decimals) / (native_coin_qty * 10^pc_decimals * price * 10^expo)
+    let pow10 = |exp: u32| 10u128.checked_pow(exp);
+    let values = (|| {
+        let fill = checked_product(&[
+            native_pc_qty as u128,
+            pow10(coin_decimals as u32)?,
+            pow10(fill_expo)?,
+            10_000,
+        ])?;
+        let band = checked_product(&[
+            native_coin_qty as u128,
+            pow10(pc_decimals as u32)?,
+            oracle_price.price as u128,
+            pow10(oracle_expo)?,
+            band_bps,
+        ])?;
+        Some((fill, band))
+    })();
+
+    let within_band = match (side, values) {
+        (Side::Bid, Some((fill, max))) => fill <= max,
+        (Side::Ask, Some((fill, min))) => fill >= min,
+        (_, None) => return Err(SaverError::OracleMathOverflow.into()),
+    };
+    if !within_band {
+        return Err(SaverError::OraclePriceBandExceeded.into());
+    }
+    Ok(())
+}
+
+fn checked_product(factors: &[u128]) -> Option<u128> {
+    factors
+        .iter()
+        .try_fold(1u128, |product, factor| product.checked_mul(*factor))
+}
@@ -1,11 +1,19 @@
 use anchor_lang::{
     prelude::*,
-    solana_program::{entrypoint::ProgramResult, program::invoke_signed, program_pack::Pack},
+    solana_program::{
+        entrypoint::ProgramResult,
+        program::{invoke, invoke_signed},
+        program_option::COption,
+        program_pack::Pack,
+        system_instruction,
+    },
 };
 use spl_token::{instruction, state};
 pub use spl_token::{state::AccountState as TokenAccountState, ID};
 use std::{io::Write, ops::Deref};
 
+use crate::error::{SaverError, SaverResult};
+
 #[derive(Debug, Clone, Copy)]
 pub struct SplToken;
 
@@ -104,3 +112,47 @@ pub fn transfer<'info>(
         seeds,
     )
 }
+
+/// Transfer from `from` signed by `authority`, which should be its owner or delegate approved for
+/// at least `amount`. Program signer is never used here, so only keys chosen by the owner can
+/// spend user tokens.
+pub fn transfer_from<'info>(
+    from: &Account<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    amount: u64,
+) -> SaverResult {
+    let is_owner = from.owner == *authority.key;
+    let is_delegate =
+        from.delegate == COption::Some(*authority.key) && from.delegated_amount >= amount;
+    if !authority.is_signer || !(is_owner || is_delegate) {
+        return Err(SaverError::InvalidWalletAuthority.into());
+    }
+    transfer(from.to_account_info(), to, authority, amount, &[])?;
+    Ok(())
+}
+
+pub fn create_account<'info>(
+    payer: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    rent: &Sysvar<'info, Rent>,
+    seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            account.key,
+            rent.minimum_balance(state::Account::LEN),
+            state::Account::LEN as u64,
+            &ID,
+        ),
+        &[payer, account.clone()],
+        seeds,
+    )?;
+    invoke(
+        &instruction::initialize_account(&ID, account.key, mint.key, owner.key)?,
+        &[account, mint, owner, rent.to_account_info()],
+    )
+}
@@ -0,0 +1,13 @@
+/// Pyth v2 price account data with fields read by `serum_saver::oracle`.
+pub fn pyth_price_data(price: i64, conf: u64, expo: i32, pub_slot: u64, trading: bool) -> Vec<u8> {
+    let mut data = vec![0; 3312];
+    data[0..4].copy_from_slice(&0xa1b2_c3d4u32.to_le_bytes()); // magic
+    data[4..8].copy_from_slice(&2u32.to_le_bytes()); // version
+    data[8..12].copy_from_slice(&3u32.to_le_bytes()); // account type: price
+    data[20..24].copy_from_slice(&expo.to_le_bytes());
+    data[208..216].copy_from_slice(&price.to_le_bytes()); // agg.price
+    data[216..224].copy_from_slice(&conf.to_le_bytes()); // agg.conf
+    data[224..228].copy_from_slice(&(trading as u32).to_le_bytes()); // agg.status
+    data[232..240].copy_from_slice(&pub_slot.to_le_bytes()); // agg.pub_slot
+    data
+}
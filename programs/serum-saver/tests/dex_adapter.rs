@@ -0,0 +1,262 @@
+use serum_dex::{
+    instruction::{MarketInstruction, SelfTradeBehavior},
+    matching::{OrderType, Side},
+};
+use serum_saver::dex::{
+    adapter::{self, v4, v5},
+    openbook,
+};
+use solana_program::{instruction::Instruction, pubkey::Pubkey, sysvar::rent};
+use std::num::NonZeroU64;
+
+fn keys(ix: &Instruction) -> Vec<(Pubkey, bool, bool)> {
+    ix.accounts
+        .iter()
+        .map(|meta| (meta.pubkey, meta.is_signer, meta.is_writable))
+        .collect()
+}
+
+#[test]
+fn init_open_orders_v4() {
+    let (program_id, open_orders, owner, market, authority) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+
+    let ix = v4::init_open_orders(&program_id, &open_orders, &owner, &market, None).unwrap();
+    assert_eq!(ix.program_id, program_id);
+    assert_eq!(
+        MarketInstruction::unpack(&ix.data),
+        Some(MarketInstruction::InitOpenOrders)
+    );
+    assert_eq!(
+        keys(&ix),
+        vec![
+            (open_orders, false, true),
+            (owner, true, false),
+            (market, false, false),
+            (market, false, false),
+        ]
+    );
+
+    let ix =
+        v4::init_open_orders(&program_id, &open_orders, &owner, &market, Some(&authority)).unwrap();
+    assert_eq!(keys(&ix)[4], (authority, true, false));
+}
+
+#[test]
+fn init_open_orders_v5() {
+    let (program_id, open_orders, owner, market, authority) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+
+    let ix = v5::init_open_orders(&program_id, &open_orders, &owner, &market, None).unwrap();
+    assert_eq!(ix.program_id, program_id);
+    assert_eq!(
+        MarketInstruction::unpack(&ix.data),
+        Some(MarketInstruction::InitOpenOrders)
+    );
+    assert_eq!(
+        keys(&ix),
+        vec![
+            (open_orders, false, true),
+            (owner, true, false),
+            (market, false, false),
+            (rent::ID, false, false),
+        ]
+    );
+
+    let ix =
+        v5::init_open_orders(&program_id, &open_orders, &owner, &market, Some(&authority)).unwrap();
+    assert_eq!(keys(&ix)[4], (authority, true, false));
+}
+
+#[test]
+fn init_open_orders_by_program_id() {
+    let (open_orders, owner, market) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+
+    // Only OpenBook id gets rent sysvar, any other id uses v0.4 layout
+    let ix = adapter::init_open_orders(&openbook::ID, &open_orders, &owner, &market, None).unwrap();
+    assert_eq!(keys(&ix)[3].0, rent::ID);
+    assert!(adapter::is_v5(&openbook::ID));
+
+    let ix = adapter::init_open_orders(&serum_saver::dex::ID, &open_orders, &owner, &market, None)
+        .unwrap();
+    assert_eq!(keys(&ix)[3].0, market);
+    assert!(!adapter::is_v5(&serum_saver::dex::ID));
+}
+
+// Expected layouts below are OpenBook v1 (serum-dex v0.5) ones:
+// https://github.com/openbook-dex/program/blob/master/dex/src/instruction.rs
+
+#[test]
+fn new_order_v5_layout() {
+    let accounts = (0..12).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+    let (market, open_orders, request_queue, event_queue, bids, asks) = (
+        accounts[0],
+        accounts[1],
+        accounts[2],
+        accounts[3],
+        accounts[4],
+        accounts[5],
+    );
+    let (payer, owner, coin_vault, pc_vault, rent, srm) = (
+        accounts[6],
+        accounts[7],
+        accounts[8],
+        accounts[9],
+        accounts[10],
+        accounts[11],
+    );
+
+    let ix = adapter::new_order(
+        &market,
+        &open_orders,
+        &request_queue,
+        &event_queue,
+        &bids,
+        &asks,
+        &payer,
+        &owner,
+        &coin_vault,
+        &pc_vault,
+        &spl_token::ID,
+        &rent,
+        Some(&srm),
+        &openbook::ID,
+        Side::Bid,
+        NonZeroU64::new(100).unwrap(),
+        NonZeroU64::new(2).unwrap(),
+        OrderType::ImmediateOrCancel,
+        7,
+        SelfTradeBehavior::AbortTransaction,
+        u16::MAX,
+        NonZeroU64::new(201).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(ix.program_id, openbook::ID);
+    assert_eq!(
+        keys(&ix),
+        vec![
+            (market, false, true),
+            (open_orders, false, true),
+            (request_queue, false, true),
+            (event_queue, false, true),
+            (bids, false, true),
+            (asks, false, true),
+            (payer, false, true),
+            (owner, true, false),
+            (coin_vault, false, true),
+            (pc_vault, false, true),
+            (spl_token::ID, false, false),
+            (rent, false, false),
+            (srm, false, false),
+        ]
+    );
+    // v0.5 reads `max_ts` only if data is longer, v0.4 data means no expiration
+    match MarketInstruction::unpack(&ix.data) {
+        Some(MarketInstruction::NewOrderV3(order)) => {
+            assert_eq!(order.limit_price.get(), 100);
+            assert_eq!(order.max_coin_qty.get(), 2);
+            assert_eq!(order.max_native_pc_qty_including_fees.get(), 201);
+            assert_eq!(order.client_order_id, 7);
+        }
+        other => panic!("unexpected instruction {:?}", other),
+    }
+}
+
+#[test]
+fn settle_funds_v5_layout() {
+    let accounts = (0..9).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+    let (market, open_orders, owner, coin_vault, coin_wallet) = (
+        accounts[0],
+        accounts[1],
+        accounts[2],
+        accounts[3],
+        accounts[4],
+    );
+    let (pc_vault, pc_wallet, referrer, vault_signer) =
+        (accounts[5], accounts[6], accounts[7], accounts[8]);
+
+    let ix = adapter::settle_funds(
+        &openbook::ID,
+        &market,
+        &spl_token::ID,
+        &open_orders,
+        &owner,
+        &coin_vault,
+        &coin_wallet,
+        &pc_vault,
+        &pc_wallet,
+        Some(&referrer),
+        &vault_signer,
+    )
+    .unwrap();
+    assert_eq!(ix.program_id, openbook::ID);
+    assert_eq!(
+        MarketInstruction::unpack(&ix.data),
+        Some(MarketInstruction::SettleFunds)
+    );
+    assert_eq!(
+        keys(&ix),
+        vec![
+            (market, false, true),
+            (open_orders, false, true),
+            (owner, true, false),
+            (coin_vault, false, true),
+            (pc_vault, false, true),
+            (coin_wallet, false, true),
+            (pc_wallet, false, true),
+            (vault_signer, false, false),
+            (spl_token::ID, false, false),
+            (referrer, false, true),
+        ]
+    );
+}
+
+#[test]
+fn consume_events_v5_layout() {
+    let (open_orders, market, event_queue, fee_receivable) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+
+    let ix = adapter::consume_events(
+        &openbook::ID,
+        vec![&open_orders],
+        &market,
+        &event_queue,
+        &fee_receivable,
+        &fee_receivable,
+        5,
+    )
+    .unwrap();
+    assert_eq!(ix.program_id, openbook::ID);
+    assert_eq!(
+        MarketInstruction::unpack(&ix.data),
+        Some(MarketInstruction::ConsumeEvents(5))
+    );
+    assert_eq!(
+        keys(&ix),
+        vec![
+            (open_orders, false, true),
+            (market, false, true),
+            (event_queue, false, true),
+            (fee_receivable, false, true),
+            (fee_receivable, false, true),
+        ]
+    );
+}
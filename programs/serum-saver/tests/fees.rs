@@ -0,0 +1,91 @@
+use anchor_lang::{prelude::Pubkey, AccountDeserialize};
+use serum_dex::{
+    fees::{stable_markets, FeeTier},
+    instruction::{msrm_token, srm_token},
+};
+use serum_saver::{
+    dex::{get_fee_rates, get_taker_fee_saved},
+    token::TokenAccount,
+};
+use solana_program::program_pack::Pack;
+use spl_token::state::{Account, AccountState};
+
+fn srm_vault(mint: Pubkey, amount: u64) -> TokenAccount {
+    let mut data = vec![0; Account::LEN];
+    Account {
+        mint,
+        amount,
+        state: AccountState::Initialized,
+        ..Account::default()
+    }
+    .pack_into_slice(&mut data);
+    TokenAccount::try_deserialize(&mut data.as_slice()).unwrap()
+}
+
+fn fee_tier(market: &Pubkey, mint: Pubkey, amount: u64) -> FeeTier {
+    get_fee_rates(market, &srm_vault(mint, amount)).fee_tier
+}
+
+#[test]
+fn fee_tier_boundaries() {
+    let market = Pubkey::new_unique();
+    // SRM has 6 decimals
+    let srm = |amount: u64| fee_tier(&market, srm_token::ID, amount * 1_000_000);
+    assert!(matches!(srm(0), FeeTier::Base));
+    assert!(matches!(
+        fee_tier(&market, srm_token::ID, 100 * 1_000_000 - 1),
+        FeeTier::Base
+    ));
+    assert!(matches!(srm(100), FeeTier::SRM2));
+    assert!(matches!(srm(999), FeeTier::SRM2));
+    assert!(matches!(srm(1_000), FeeTier::SRM3));
+    assert!(matches!(srm(9_999), FeeTier::SRM3));
+    assert!(matches!(srm(10_000), FeeTier::SRM4));
+    assert!(matches!(srm(99_999), FeeTier::SRM4));
+    assert!(matches!(srm(100_000), FeeTier::SRM5));
+    assert!(matches!(srm(999_999), FeeTier::SRM5));
+    assert!(matches!(srm(1_000_000), FeeTier::SRM6));
+
+    // MSRM has no decimals, one MSRM is enough
+    assert!(matches!(
+        fee_tier(&market, msrm_token::ID, 0),
+        FeeTier::Base
+    ));
+    assert!(matches!(
+        fee_tier(&market, msrm_token::ID, 1),
+        FeeTier::MSRM
+    ));
+
+    // Stable markets have own tier regardless of balance
+    let stable = stable_markets::usdt_usdc::ID;
+    assert!(matches!(
+        fee_tier(&stable, srm_token::ID, 0),
+        FeeTier::Stable
+    ));
+    assert!(matches!(
+        fee_tier(&stable, msrm_token::ID, 1),
+        FeeTier::Stable
+    ));
+}
+
+#[test]
+fn taker_fee() {
+    let market = Pubkey::new_unique();
+    let base = get_fee_rates(&market, &srm_vault(srm_token::ID, 0));
+    assert_eq!(base.taker_fee(1_000_000), 2_200);
+    // rounded up
+    assert_eq!(base.taker_fee(1), 1);
+    assert_eq!(base.remove_taker_fee(1_002_200), 1_000_000);
+
+    let srm2 = get_fee_rates(&market, &srm_vault(srm_token::ID, 100 * 1_000_000));
+    assert_eq!(srm2.taker_fee(1_000_000), 2_000);
+    assert_eq!(srm2.remove_taker_fee(1_002_000), 1_000_000);
+}
+
+#[test]
+fn taker_fee_saved() {
+    // 2 bps between base and SRM2 of matched quantity, same for both sides
+    assert_eq!(get_taker_fee_saved(FeeTier::SRM2, 1_000_000), 200);
+    assert_eq!(get_taker_fee_saved(FeeTier::Base, 1_000_000), 0);
+    assert_eq!(get_taker_fee_saved(FeeTier::Stable, 1_000_000), 0);
+}
@@ -0,0 +1,77 @@
+use serum_dex::matching::Side;
+use serum_saver::{
+    error::SaverError,
+    oracle::{check_price_band, load_price, OraclePrice},
+};
+
+use pyth::pyth_price_data;
+
+#[path = "common/pyth.rs"]
+mod pyth;
+
+#[test]
+fn load_trading_price() {
+    let price = load_price(&pyth_price_data(12_345, 7, -3, 100, true), 110, 10).unwrap();
+    assert_eq!(
+        price,
+        OraclePrice {
+            price: 12_345,
+            conf: 7,
+            expo: -3
+        }
+    );
+
+    assert!(load_price(&pyth_price_data(12_345, 7, -3, 100, false), 100, 10).is_err());
+    assert!(load_price(&pyth_price_data(0, 7, -3, 100, true), 100, 10).is_err());
+    assert!(load_price(&[0; 64], 100, 10).is_err());
+}
+
+#[test]
+fn stale_price() {
+    let data = pyth_price_data(12_345, 7, -3, 100, true);
+    assert!(load_price(&data, 110, 10).is_ok());
+    assert_eq!(
+        load_price(&data, 111, 10).unwrap_err(),
+        SaverError::OraclePriceStale.into()
+    );
+}
+
+#[test]
+fn price_band() {
+    // 2.00 pc per coin, coin with 9 decimals and pc with 6 decimals
+    let price = OraclePrice {
+        price: 200,
+        conf: 0,
+        expo: -2,
+    };
+    let check = |side, native_pc_qty, band_bps| {
+        check_price_band(&price, side, 1_000_000_000, native_pc_qty, 9, 6, band_bps).is_ok()
+    };
+
+    assert!(check(Side::Bid, 2_000_000, 0));
+    assert!(check(Side::Bid, 2_020_000, 100));
+    assert!(!check(Side::Bid, 2_020_001, 100));
+    assert!(check(Side::Bid, 1_000_000, 0));
+
+    assert!(check(Side::Ask, 1_980_000, 100));
+    assert!(!check(Side::Ask, 1_979_999, 100));
+    assert!(check(Side::Ask, 3_000_000, 0));
+}
+
+#[test]
+fn confidence_wider_than_band() {
+    // 2.00 +- 0.02
+    let price = OraclePrice {
+        price: 200,
+        conf: 2,
+        expo: -2,
+    };
+    let check =
+        |band_bps| check_price_band(&price, Side::Bid, 1_000_000_000, 2_000_000, 9, 6, band_bps);
+
+    assert!(check(100).is_ok());
+    assert_eq!(
+        check(99).unwrap_err(),
+        SaverError::OracleConfidenceTooWide.into()
+    );
+}
@@ -1,65 +1,31 @@
 #![allow(unaligned_references)]
 
-use serum_dex::matching::Side;
+use anchor_lang::__private::ErrorCode;
+use serum_dex::{fees::FeeTier, matching::Side};
+use serum_saver::{
+    dex::{openbook, sort_open_orders},
+    error::SaverError,
+};
 use solana_program_test::tokio;
-use solana_sdk::signature::Signer;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
 
-use util::{token_balance, token_balance2, TestContext, UtilResult};
+use util::{
+    assert_error, setup, setup_permissioned, token_balance, token_balance2, Playground, UtilResult,
+    ASK_PRICE, BID_PRICE, DEX_PROGRAM, ORACLE,
+};
 
 mod util;
 
 #[tokio::test]
 async fn playground() -> UtilResult<()> {
-    // let tc = TestContext::new(None).await?;
-    let tc = TestContext::new(Some("warn")).await?;
-    let dex = tc.gen_serum_dex().await?;
-
-    // Maker
-    let open_orders_maker_key = dex.gen_open_orders(&tc.alice).await?;
-    let (bid_price, ask_price) = (198, 202);
-    dex.add_liquidity(
-        &open_orders_maker_key,
-        &tc.alice,
-        Side::Bid,
-        bid_price,
-        100 * dex.coin_lots,
-    )
-    .await?;
-    dex.add_liquidity(
-        &open_orders_maker_key,
-        &tc.alice,
-        Side::Ask,
-        ask_price,
-        100 * dex.coin_lots,
-    )
-    .await?;
-
-    let coin_vault = tc.coin_token.get_associated_token_address(&tc.bob.pubkey());
-    tc.coin_token
-        .mint_to(
-            &coin_vault,
-            &tc.coin_token_authority,
-            1_000_000 * u64::pow(10, tc.coin_token_decimals as u32),
-        )
-        .await?;
-    let pc_vault = tc.pc_token.get_associated_token_address(&tc.bob.pubkey());
-    tc.pc_token
-        .mint_to(
-            &pc_vault,
-            &tc.pc_token_authority,
-            1_000_000 * u64::pow(10, tc.pc_token_decimals as u32),
-        )
-        .await?;
-
-    let saver = tc.gen_saver().await?;
-    // tc.srm_token
-    //     .mint_to(
-    //         &saver.srm_vault,
-    //         &tc.srm_token_authhority,
-    //         200 * u64::pow(10, tc.srm_token_decimals as u32),
-    //     )
-    //     .await?;
-    let saver_market = saver.gen_market(&dex).await?;
+    let Playground {
+        tc,
+        dex,
+        saver,
+        saver_market,
+        ..
+    } = setup().await?;
+    let bob_stats = saver.gen_user_stats(&tc.bob).await?;
 
     println!(
         "saver coin: {:?}",
@@ -74,7 +40,9 @@ async fn playground() -> UtilResult<()> {
         token_balance(&tc.coin_token, &tc.bob).await?
     );
     println!("bob pc: {:?}", token_balance(&tc.pc_token, &tc.bob).await?);
-    saver_market.buy(204, dex.coin_lots, &tc.bob).await?;
+    saver_market
+        .swap_with_stats(Side::Bid, 204, dex.coin_lots, &tc.bob, Some(bob_stats))
+        .await?;
     println!("swap");
     println!(
         "saver coin: {:?}",
@@ -89,7 +57,9 @@ async fn playground() -> UtilResult<()> {
         token_balance(&tc.coin_token, &tc.bob).await?
     );
     println!("bob pc: {:?}", token_balance(&tc.pc_token, &tc.bob).await?);
-    saver_market.sell(196, dex.coin_lots, &tc.bob).await?;
+    saver_market
+        .swap_with_stats(Side::Ask, 196, dex.coin_lots, &tc.bob, Some(bob_stats))
+        .await?;
     println!("swap");
     println!(
         "saver coin: {:?}",
@@ -105,6 +75,10 @@ async fn playground() -> UtilResult<()> {
     );
     println!("bob pc: {:?}", token_balance(&tc.pc_token, &tc.bob).await?);
 
+    let stats = saver.get_user_stats(bob_stats).await?;
+    assert_eq!(stats.swap_count, 2);
+    assert_eq!(stats.coin_volume, 2 * dex.coin_lots * dex.coin_lot_size);
+
     // // Taker
     // let open_orders_taker_key = dex.gen_open_orders(&tc.bob).await?;
     // dex.make_swap(
@@ -209,3 +183,751 @@ async fn playground() -> UtilResult<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn delegated_swap() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver,
+        saver_market,
+        ..
+    } = setup().await?;
+    let bob_stats = saver.gen_user_stats(&tc.bob).await?;
+
+    // Approved saver signer does not let anybody spend Bob tokens
+    let bob_pc_wallet = tc.pc_token.get_associated_token_address(&tc.bob.pubkey());
+    saver
+        .approve(&tc.bob, bob_pc_wallet, &saver.signer, u64::MAX)
+        .await?;
+    assert_error(
+        saver_market
+            .swap_signed(
+                Side::Bid,
+                ASK_PRICE,
+                dex.coin_lots,
+                &tc.bob.pubkey(),
+                &tc.alice,
+                &[bob_stats],
+            )
+            .await,
+        SaverError::InvalidWalletAuthority,
+    );
+
+    // Alice swaps on behalf of Bob as approved delegate
+    saver
+        .approve(&tc.bob, bob_pc_wallet, &tc.alice.pubkey(), u64::MAX)
+        .await?;
+    saver_market
+        .swap_signed(
+            Side::Bid,
+            ASK_PRICE,
+            dex.coin_lots,
+            &tc.bob.pubkey(),
+            &tc.alice,
+            &[bob_stats],
+        )
+        .await?;
+    assert_eq!(saver.get_user_stats(bob_stats).await?.swap_count, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn allowlist() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver,
+        saver_market,
+        ..
+    } = setup().await?;
+
+    // Only allowed wallets can swap with enabled allowlist
+    saver.set_allowlist_enabled(true).await?;
+    assert_error(
+        saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await,
+        SaverError::WalletNotAllowed,
+    );
+    let bob_allowlist_entry = saver.add_to_allowlist(&tc.bob).await?;
+    saver_market
+        .swap_signed(
+            Side::Bid,
+            ASK_PRICE,
+            dex.coin_lots,
+            &tc.bob.pubkey(),
+            &tc.bob,
+            &[bob_allowlist_entry],
+        )
+        .await?;
+
+    saver.set_allowlist_enabled(false).await?;
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_notional_per_swap() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver_market,
+        ..
+    } = setup().await?;
+
+    // Swap notional is limited by saver market
+    let notional = ASK_PRICE * dex.coin_lots * dex.pc_lot_size;
+    saver_market.set_limits(notional - 1, 0, 0).await?;
+    assert_error(
+        saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await,
+        SaverError::MaxNotionalExceeded,
+    );
+    saver_market.set_limits(notional, 0, 0).await?;
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn oracle_price_band() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver_market,
+        ..
+    } = setup().await?;
+
+    // Oracle price is 2.00, ask is 2.02
+    saver_market.set_oracle(*ORACLE, 50, 25).await?;
+    assert_error(
+        saver_market
+            .swap_signed(
+                Side::Bid,
+                ASK_PRICE,
+                dex.coin_lots,
+                &tc.bob.pubkey(),
+                &tc.bob,
+                &[*ORACLE],
+            )
+            .await,
+        SaverError::OraclePriceBandExceeded,
+    );
+    assert_error(
+        saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await,
+        SaverError::OracleRequired,
+    );
+    saver_market.set_oracle(*ORACLE, 100, 25).await?;
+    saver_market
+        .swap_signed(
+            Side::Bid,
+            ASK_PRICE,
+            dex.coin_lots,
+            &tc.bob.pubkey(),
+            &tc.bob,
+            &[*ORACLE],
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn oracle_price_should_be_fresh_and_certain() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver_market,
+        ..
+    } = setup().await?;
+    let bob = tc.bob.pubkey();
+
+    // Confidence interval is 0.01, so band below 50 bps can not be checked
+    saver_market.set_oracle(*ORACLE, 40, 25).await?;
+    assert_error(
+        saver_market
+            .swap_signed(
+                Side::Bid,
+                ASK_PRICE,
+                dex.coin_lots,
+                &bob,
+                &tc.bob,
+                &[*ORACLE],
+            )
+            .await,
+        SaverError::OracleConfidenceTooWide,
+    );
+
+    // Oracle price is published in genesis slot
+    saver_market.set_oracle(*ORACLE, 100, 0).await?;
+    tc.warp_to_next_slot().await?;
+    assert_error(
+        saver_market
+            .swap_signed(
+                Side::Bid,
+                ASK_PRICE,
+                2 * dex.coin_lots,
+                &bob,
+                &tc.bob,
+                &[*ORACLE],
+            )
+            .await,
+        SaverError::OraclePriceStale,
+    );
+    saver_market.set_oracle(*ORACLE, 100, 25).await?;
+    saver_market
+        .swap_signed(
+            Side::Bid,
+            ASK_PRICE,
+            3 * dex.coin_lots,
+            &bob,
+            &tc.bob,
+            &[*ORACLE],
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn crank_without_fees() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver_market,
+        maker_open_orders,
+        ..
+    } = setup().await?;
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+
+    // Nothing is collected yet, so crank is not tipped
+    saver_market.set_crank_tip(100).await?;
+    let bob_pc_wallet = tc.pc_token.get_associated_token_address(&tc.bob.pubkey());
+    let bob_pc_balance = token_balance(&tc.pc_token, &tc.bob).await?;
+    saver_market.crank(&bob_pc_wallet).await?;
+    assert_eq!(token_balance(&tc.pc_token, &tc.bob).await?, bob_pc_balance);
+    assert_error(
+        saver_market
+            .with_open_orders(maker_open_orders)
+            .crank(&bob_pc_wallet)
+            .await,
+        ErrorCode::ConstraintHasOne,
+    );
+    let bob_coin_wallet = tc.coin_token.get_associated_token_address(&tc.bob.pubkey());
+    assert_error(
+        saver_market.crank(&bob_coin_wallet).await,
+        SaverError::InvalidTipWallet,
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn crank_tip() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver_market,
+        maker_open_orders,
+        ..
+    } = setup().await?;
+    let bob_pc_wallet = tc.pc_token.get_associated_token_address(&tc.bob.pubkey());
+
+    // Consumed taker events of the saver accrue referrer rebates, which are settled to fees
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+    dex.consume_events(vec![&maker_open_orders]).await?;
+    saver_market.crank(&bob_pc_wallet).await?;
+    saver_market.settle().await?;
+    let pc_fees = saver_market.get_state().await?.pc_fees;
+    assert!(pc_fees > 1);
+
+    // Tip is paid from fees
+    saver_market.set_crank_tip(1).await?;
+    saver_market
+        .buy(ASK_PRICE, 2 * dex.coin_lots, &tc.bob)
+        .await?;
+    dex.consume_events(vec![&maker_open_orders]).await?;
+    let bob_pc_balance = token_balance(&tc.pc_token, &tc.bob).await?;
+    saver_market.crank(&bob_pc_wallet).await?;
+    assert_eq!(
+        token_balance(&tc.pc_token, &tc.bob).await?,
+        bob_pc_balance + 1
+    );
+    assert_eq!(saver_market.get_state().await?.pc_fees, pc_fees - 1);
+
+    // Only one crank is tipped in a slot
+    saver_market
+        .buy(ASK_PRICE, 3 * dex.coin_lots, &tc.bob)
+        .await?;
+    dex.consume_events(vec![&maker_open_orders]).await?;
+    let bob_pc_balance = token_balance(&tc.pc_token, &tc.bob).await?;
+    saver_market.crank(&bob_pc_wallet).await?;
+    assert_eq!(token_balance(&tc.pc_token, &tc.bob).await?, bob_pc_balance);
+    assert_eq!(saver_market.get_state().await?.pc_fees, pc_fees - 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn settle_without_fees() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver_market,
+        ..
+    } = setup().await?;
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+    saver_market.sell(BID_PRICE, dex.coin_lots, &tc.bob).await?;
+
+    // Swaps settle own funds, so nothing is left for fees
+    saver_market.settle().await?;
+    assert_eq!(
+        token_balance2(&tc.pc_token, saver_market.pc_vault).await?,
+        0
+    );
+    assert_eq!(
+        token_balance2(&tc.coin_token, saver_market.coin_vault).await?,
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn withdraw_fees() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver_market,
+        maker_open_orders,
+        ..
+    } = setup().await?;
+    let bob_pc_wallet = tc.pc_token.get_associated_token_address(&tc.bob.pubkey());
+
+    // Referrer rebates of the saver are settled to fees
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+    dex.consume_events(vec![&maker_open_orders]).await?;
+    saver_market.crank(&bob_pc_wallet).await?;
+    saver_market.settle().await?;
+    let pc_fees = saver_market.get_state().await?.pc_fees;
+    assert!(pc_fees > 0);
+    let pc_vault_balance = token_balance2(&tc.pc_token, saver_market.pc_vault).await?;
+    let payer_pc_balance = token_balance(&tc.pc_token, &tc.payer).await?;
+
+    let payer = tc.payer.pubkey();
+    assert_error(
+        saver_market
+            .withdraw_fees(&tc.bob, &tc.bob.pubkey(), 0, pc_fees)
+            .await,
+        ErrorCode::ConstraintHasOne,
+    );
+    assert_error(
+        saver_market
+            .withdraw_fees(&tc.payer, &payer, 0, pc_fees + 1)
+            .await,
+        SaverError::WithdrawExceedsFees,
+    );
+    assert_error(
+        saver_market.withdraw_fees(&tc.payer, &payer, 1, 0).await,
+        SaverError::WithdrawExceedsFees,
+    );
+    saver_market
+        .withdraw_fees(&tc.payer, &payer, 0, pc_fees)
+        .await?;
+    assert_eq!(
+        token_balance(&tc.pc_token, &tc.payer).await?,
+        payer_pc_balance + pc_fees
+    );
+    assert_eq!(saver_market.get_state().await?.pc_fees, 0);
+    assert_eq!(
+        token_balance2(&tc.pc_token, saver_market.pc_vault).await?,
+        pc_vault_balance - pc_fees
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn permissioned_market() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver,
+        saver_market,
+        maker_open_orders,
+    } = setup_permissioned().await?;
+    let bob_pc_wallet = tc.pc_token.get_associated_token_address(&tc.bob.pubkey());
+
+    let bob_coin_balance = token_balance(&tc.coin_token, &tc.bob).await?;
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+    assert_eq!(
+        token_balance(&tc.coin_token, &tc.bob).await? - bob_coin_balance,
+        dex.coin_lots * dex.coin_lot_size
+    );
+    saver_market.settle().await?;
+    // Saver signer consumes events as prune authority, the queue starts with the maker event of
+    // Alice, so nothing is consumed
+    saver_market.crank(&bob_pc_wallet).await?;
+
+    // Market authority consumes events of Alice and the saver, so the saver OpenOrders accrues
+    // referrer rebate
+    let market_authority = dex.market_authority.as_ref().unwrap().pubkey();
+    dex.set_market_authorities(&saver.signer, &market_authority)
+        .await?;
+    let mut open_orders = vec![maker_open_orders, saver_market.open_orders];
+    sort_open_orders(&mut open_orders);
+    dex.consume_events_permissioned(&open_orders.iter().collect::<Vec<_>>())
+        .await?;
+
+    // Crank and new markets are rejected once the saver is not market authority
+    assert_error(
+        saver_market.crank(&bob_pc_wallet).await,
+        SaverError::InvalidPruneAuthority,
+    );
+    assert_error(
+        saver.gen_market(&dex).await,
+        SaverError::InvalidPruneAuthority,
+    );
+    let other = Pubkey::new_unique();
+    dex.set_market_authorities(&other, &other).await?;
+    assert_error(
+        saver_market.crank(&bob_pc_wallet).await,
+        SaverError::InvalidOpenOrdersAuthority,
+    );
+    assert_error(
+        saver.gen_market(&dex).await,
+        SaverError::InvalidOpenOrdersAuthority,
+    );
+
+    // Settle still recovers funds of the saver
+    saver_market.settle().await?;
+    let pc_fees = saver_market.get_state().await?.pc_fees;
+    assert!(pc_fees > 0);
+    assert_eq!(
+        token_balance2(&tc.pc_token, saver_market.pc_vault).await?,
+        pc_fees
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn saver_accepts_own_dex_markets_only() -> UtilResult<()> {
+    let Playground { tc, dex, .. } = setup().await?;
+
+    let other_saver = tc.gen_saver_with_dex_program(spl_token::ID).await?;
+    assert_error(
+        other_saver.gen_market(&dex).await,
+        ErrorCode::ConstraintHasOne,
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn min_fee_tier() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver,
+        saver_market,
+        ..
+    } = setup().await?;
+
+    // Empty SRM vault gives `Base` tier only
+    saver.set_min_fee_tier(FeeTier::SRM2).await?;
+    assert_error(
+        saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await,
+        SaverError::FeeTierTooLow,
+    );
+    saver.set_min_fee_tier(FeeTier::Base).await?;
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn market_vaults_are_isolated() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver,
+        saver_market,
+        ..
+    } = setup().await?;
+
+    // Markets with the same mints get own vaults, so swaps through one market never touch
+    // balances of another
+    let other_market = saver.gen_market(&dex).await?;
+    assert_ne!(other_market.coin_vault, saver_market.coin_vault);
+    assert_ne!(other_market.pc_vault, saver_market.pc_vault);
+
+    tc.pc_token
+        .mint_to(&other_market.pc_vault, &tc.pc_token_authority, 1_000)
+        .await?;
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+    saver_market.sell(BID_PRICE, dex.coin_lots, &tc.bob).await?;
+    assert_eq!(
+        token_balance2(&tc.pc_token, saver_market.pc_vault).await?,
+        0
+    );
+    assert_eq!(
+        token_balance2(&tc.pc_token, other_market.pc_vault).await?,
+        1_000
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn market_mints_should_match_dex_market() -> UtilResult<()> {
+    let Playground { tc, dex, saver, .. } = setup().await?;
+
+    let (coin_mint, pc_mint) = (*tc.coin_token.get_address(), *tc.pc_token.get_address());
+    assert_error(
+        saver.gen_market_with_mints(&dex, pc_mint, coin_mint).await,
+        SaverError::InvalidMarketMint,
+    );
+    assert_error(
+        saver
+            .gen_market_with_mints(&dex, coin_mint, coin_mint)
+            .await,
+        SaverError::InvalidMarketMint,
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn swap_takes_only_simulated_spend() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver_market,
+        maker_open_orders,
+        ..
+    } = setup().await?;
+    dex.add_liquidity(
+        &maker_open_orders,
+        &tc.alice,
+        Side::Ask,
+        ASK_PRICE + 2,
+        100 * dex.coin_lots,
+    )
+    .await?;
+    dex.add_liquidity(
+        &maker_open_orders,
+        &tc.alice,
+        Side::Ask,
+        ASK_PRICE + 4,
+        100 * dex.coin_lots,
+    )
+    .await?;
+
+    // Orders are matched from the best price up to the limit price, and Bob pays only for them
+    // instead of max pc quantity of the limit price
+    let bob_coin_balance = token_balance(&tc.coin_token, &tc.bob).await?;
+    let bob_pc_balance = token_balance(&tc.pc_token, &tc.bob).await?;
+    saver_market
+        .buy(ASK_PRICE + 2, 150 * dex.coin_lots, &tc.bob)
+        .await?;
+    let native_pc_qty = (100 * ASK_PRICE + 50 * (ASK_PRICE + 2)) * dex.coin_lots * dex.pc_lot_size;
+    assert_eq!(
+        bob_pc_balance - token_balance(&tc.pc_token, &tc.bob).await?,
+        native_pc_qty + FeeTier::Base.taker_fee(native_pc_qty)
+    );
+    assert_eq!(
+        token_balance(&tc.coin_token, &tc.bob).await? - bob_coin_balance,
+        150 * dex.coin_lots * dex.coin_lot_size
+    );
+    assert_eq!(
+        token_balance2(&tc.pc_token, saver_market.pc_vault).await?,
+        0
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn swap_settles_leftover_to_fees() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver_market,
+        maker_open_orders,
+        ..
+    } = setup().await?;
+    let bob_pc_wallet = tc.pc_token.get_associated_token_address(&tc.bob.pubkey());
+
+    // Cranked taker event leaves referrer rebate in saver OpenOrders, which is not settled yet
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+    dex.consume_events(vec![&maker_open_orders]).await?;
+    saver_market.crank(&bob_pc_wallet).await?;
+    assert_eq!(saver_market.get_state().await?.pc_fees, 0);
+
+    // Next swapper gets only own fill, leftover goes to fees
+    let bob_coin_balance = token_balance(&tc.coin_token, &tc.bob).await?;
+    let bob_pc_balance = token_balance(&tc.pc_token, &tc.bob).await?;
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+    let native_pc_qty = ASK_PRICE * dex.coin_lots * dex.pc_lot_size;
+    assert_eq!(
+        bob_pc_balance - token_balance(&tc.pc_token, &tc.bob).await?,
+        native_pc_qty + FeeTier::Base.taker_fee(native_pc_qty)
+    );
+    assert_eq!(
+        token_balance(&tc.coin_token, &tc.bob).await? - bob_coin_balance,
+        dex.coin_lots * dex.coin_lot_size
+    );
+
+    let state = saver_market.get_state().await?;
+    assert!(state.pc_fees > 0);
+    assert_eq!(state.coin_fees, 0);
+    assert_eq!(
+        token_balance2(&tc.pc_token, saver_market.pc_vault).await?,
+        state.pc_fees
+    );
+    assert_eq!(
+        token_balance2(&tc.coin_token, saver_market.coin_vault).await?,
+        0
+    );
+
+    Ok(())
+}
+
+/// Runs with `make test-bpf-openbook`: swaps place `NewOrderV3` and `SettleFunds` built by
+/// `dex::adapter` to OpenBook program.
+#[tokio::test]
+async fn openbook_new_order_and_settle() -> UtilResult<()> {
+    if *DEX_PROGRAM != openbook::ID {
+        return Ok(());
+    }
+    let Playground {
+        tc,
+        dex,
+        saver_market,
+        maker_open_orders,
+        ..
+    } = setup().await?;
+    let bob_pc_wallet = tc.pc_token.get_associated_token_address(&tc.bob.pubkey());
+
+    let bob_coin_balance = token_balance(&tc.coin_token, &tc.bob).await?;
+    let bob_pc_balance = token_balance(&tc.pc_token, &tc.bob).await?;
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+    let native_pc_qty = ASK_PRICE * dex.coin_lots * dex.pc_lot_size;
+    assert_eq!(
+        bob_pc_balance - token_balance(&tc.pc_token, &tc.bob).await?,
+        native_pc_qty + FeeTier::Base.taker_fee(native_pc_qty)
+    );
+    assert_eq!(
+        token_balance(&tc.coin_token, &tc.bob).await? - bob_coin_balance,
+        dex.coin_lots * dex.coin_lot_size
+    );
+
+    let bob_pc_balance = token_balance(&tc.pc_token, &tc.bob).await?;
+    saver_market.sell(BID_PRICE, dex.coin_lots, &tc.bob).await?;
+    let native_pc_qty = BID_PRICE * dex.coin_lots * dex.pc_lot_size;
+    assert_eq!(
+        token_balance(&tc.pc_token, &tc.bob).await? - bob_pc_balance,
+        native_pc_qty - FeeTier::Base.taker_fee(native_pc_qty)
+    );
+    assert_eq!(
+        token_balance(&tc.coin_token, &tc.bob).await?,
+        bob_coin_balance
+    );
+
+    // Referrer rebates of consumed taker events are settled by OpenBook to fees
+    dex.consume_events(vec![&maker_open_orders]).await?;
+    saver_market.crank(&bob_pc_wallet).await?;
+    saver_market.settle().await?;
+    let pc_fees = saver_market.get_state().await?.pc_fees;
+    assert!(pc_fees > 0);
+    assert_eq!(
+        token_balance2(&tc.pc_token, saver_market.pc_vault).await?,
+        pc_fees
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_swaps_per_slot() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver_market,
+        ..
+    } = setup().await?;
+
+    // Counter is reset in the next slot
+    saver_market.set_limits(0, 1, 0).await?;
+    saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await?;
+    assert_error(
+        saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await,
+        SaverError::MaxSwapsPerSlotExceeded,
+    );
+    tc.warp_to_next_slot().await?;
+    saver_market
+        .buy(ASK_PRICE, 2 * dex.coin_lots, &tc.bob)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_daily_volume_per_user() -> UtilResult<()> {
+    let Playground {
+        tc,
+        dex,
+        saver_market,
+        ..
+    } = setup().await?;
+
+    let notional = ASK_PRICE * dex.coin_lots * dex.pc_lot_size;
+    saver_market.set_limits(0, 0, 2 * notional).await?;
+    assert_error(
+        saver_market.buy(ASK_PRICE, dex.coin_lots, &tc.bob).await,
+        SaverError::UserVolumeRequired,
+    );
+
+    // Volume is tracked per wallet, UserVolume of another wallet is not accepted
+    let bob_volume = saver_market.gen_user_volume(&tc.bob).await?;
+    saver_market
+        .swap_signed(
+            Side::Bid,
+            ASK_PRICE,
+            2 * dex.coin_lots,
+            &tc.bob.pubkey(),
+            &tc.bob,
+            &[bob_volume],
+        )
+        .await?;
+    assert_error(
+        saver_market
+            .swap_signed(
+                Side::Bid,
+                ASK_PRICE,
+                dex.coin_lots,
+                &tc.bob.pubkey(),
+                &tc.bob,
+                &[bob_volume],
+            )
+            .await,
+        SaverError::MaxDailyVolumeExceeded,
+    );
+    let alice_volume = saver_market.gen_user_volume(&tc.alice).await?;
+    assert_error(
+        saver_market
+            .swap_signed(
+                Side::Bid,
+                ASK_PRICE,
+                dex.coin_lots,
+                &tc.bob.pubkey(),
+                &tc.bob,
+                &[alice_volume],
+            )
+            .await,
+        SaverError::UserVolumeRequired,
+    );
+
+    Ok(())
+}
@@ -1,33 +1,36 @@
 #![allow(dead_code)]
-use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
 use futures::future::{try_join, try_join_all};
 use rand_chacha::{
     rand_core::{RngCore as _, SeedableRng as _},
     ChaCha20Rng,
 };
 use serum_dex::{
+    fees::FeeTier,
     instruction::{srm_token::ID as SerumTokenId, SelfTradeBehavior},
     matching::{OrderType, Side},
-    state::{gen_vault_signer_key, OpenOrders},
+    state::{gen_vault_signer_key, Market, MarketStateV2, OpenOrders},
 };
+use serum_saver::{SaverMarket, UserStats};
 use solana_program_test::{
     processor, tokio::sync::Mutex, BanksClient, ProgramTest, ProgramTestContext,
 };
 use solana_sdk::{
-    account::Account,
+    account::{Account, AccountSharedData},
     account_info::{Account as _, AccountInfo},
     entrypoint::ProgramResult,
     hash::hashv,
-    instruction::Instruction,
+    instruction::{AccountMeta, Instruction, InstructionError},
     native_token::sol_to_lamports,
+    program_error::ProgramError,
     program_option::COption,
     program_pack::Pack,
     pubkey::Pubkey,
     signature::Signer,
     signer::{keypair::Keypair, signers::Signers},
-    system_instruction, system_program,
-    transaction::Transaction,
-    transport::Result as TransportResult,
+    system_instruction, system_program, sysvar,
+    transaction::{Transaction, TransactionError},
+    transport::{Result as TransportResult, TransportError},
 };
 use spl_token::state::Mint as TokenMint;
 use spl_token_client::{
@@ -35,7 +38,8 @@ use spl_token_client::{
     token::Token,
 };
 use std::{
-    env::{current_dir, set_current_dir},
+    env::{self, current_dir, set_current_dir},
+    fmt::Debug,
     mem::size_of,
     num::NonZeroU64,
     ops::{Deref, DerefMut},
@@ -43,13 +47,29 @@ use std::{
     sync::{Arc, Mutex as SyncMutex},
 };
 
+use pyth::pyth_price_data;
+
+#[path = "common/pyth.rs"]
+mod pyth;
+
 pub type UtilError = Box<dyn std::error::Error>;
 pub type UtilResult<T = ()> = Result<T, UtilError>;
 
 lazy_static::lazy_static! {
+    /// Tests run in parallel, but cwd is shared by the process.
+    static ref CWD_LOCK: SyncMutex<()> = SyncMutex::new(());
     static ref SRM_TOKEN_DECIMALS: u8 = 6;
     static ref SRM_TOKEN_AUTHORITY: Keypair =
         Keypair::from_base58_string("4cizbpotMo3hC9GvMKG8yZYAQ1UACCVvQAoNQdc3y4zbKsm8frfDC2SdyjTiK8WRp626mWsKw94wudeo2TLvqXPE");
+    /// Pyth price account with price 2.00 of coin in pc.
+    pub static ref ORACLE: Pubkey = Pubkey::new_unique();
+    /// OpenBook if `SAVER_TEST_DEX=openbook` (see `make test-bpf-openbook`), serum-dex otherwise.
+    static ref OPENBOOK: bool = env::var("SAVER_TEST_DEX").map_or(false, |dex| dex == "openbook");
+    pub static ref DEX_PROGRAM: Pubkey = if *OPENBOOK {
+        serum_saver::dex::openbook::ID
+    } else {
+        serum_saver::dex::ID
+    };
 }
 
 fn create_program_test() -> UtilResult<ProgramTest> {
@@ -59,11 +79,13 @@ fn create_program_test() -> UtilResult<ProgramTest> {
     pt.prefer_bpf(true);
 
     // `serum_dex.so` not in `target/deploy`, so change cwd
+    // OpenBook keeps crate name, so it's `serum_dex.so` too
+    let _cwd_lock = CWD_LOCK.lock().unwrap();
     let cwd = current_dir()?;
-    set_current_dir(into_serum_dex_deploy_dir(cwd.clone()))?;
+    set_current_dir(into_dex_deploy_dir(cwd.clone()))?;
     pt.add_program(
         "serum_dex",
-        serum_saver::dex::ID,
+        *DEX_PROGRAM,
         processor!(dex_process_instruction),
     );
     set_current_dir(cwd)?;
@@ -93,13 +115,41 @@ fn create_program_test() -> UtilResult<ProgramTest> {
     .expect("failed to pack srm_token");
     pt.add_account(SerumTokenId, srm_token_account);
 
+    let mut oracle_account = Account::new(sol_to_lamports(1.0), 0, &Pubkey::new_unique());
+    // 2.00 +- 0.01, published in genesis slot
+    oracle_account.data = pyth_price_data(200, 1, -2, 0, true);
+    pt.add_account(*ORACLE, oracle_account);
+
     Ok(pt)
 }
 
-fn into_serum_dex_deploy_dir(mut current_dir: PathBuf) -> PathBuf {
+/// Fails if `result` is not failed instruction with `error` code, e.g. `SaverError`. Checking
+/// only `is_err()` is not enough, since the same call can fail on unrelated constraint.
+pub fn assert_error<T: Debug>(result: UtilResult<T>, error: impl Into<ProgramError>) {
+    let expected = match error.into() {
+        ProgramError::Custom(code) => InstructionError::Custom(code),
+        error => panic!("expected custom program error, got {:?}", error),
+    };
+    match result {
+        Ok(value) => panic!("expected {:?}, got Ok({:?})", expected, value),
+        Err(error) => match error.downcast_ref::<TransportError>() {
+            Some(TransportError::TransactionError(TransactionError::InstructionError(
+                _,
+                actual,
+            ))) => assert_eq!(*actual, expected),
+            _ => panic!("expected {:?}, got {}", expected, error),
+        },
+    }
+}
+
+fn into_dex_deploy_dir(mut current_dir: PathBuf) -> PathBuf {
     current_dir.pop();
     current_dir.pop();
-    current_dir.push(PathBuf::from("contrib/serum-dex/dex/target/deploy"));
+    current_dir.push(PathBuf::from(if *OPENBOOK {
+        "contrib/openbook-dex/dex/target/deploy"
+    } else {
+        "contrib/serum-dex/dex/target/deploy"
+    }));
     current_dir
 }
 
@@ -200,14 +250,26 @@ async fn process_ixs<T: Signers>(
     payer: &Pubkey,
     signing_keypairs: &T,
 ) -> TransportResult<()> {
-    let recent_blockhash = banks_client.get_recent_blockhash().await?;
-    let transaction = Transaction::new_signed_with_payer(
-        instructions,
-        Some(payer),
-        signing_keypairs,
-        recent_blockhash,
-    );
-    banks_client.process_transaction(transaction).await
+    let mut recent_blockhash = banks_client.get_recent_blockhash().await?;
+    loop {
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(payer),
+            signing_keypairs,
+            recent_blockhash,
+        );
+        // Identical transaction with the same blockhash is not executed again, banks client
+        // returns result of the first one (e.g. retry after failed swap), so wait for new one.
+        if banks_client
+            .get_transaction_status(transaction.signatures[0])
+            .await?
+            .is_some()
+        {
+            recent_blockhash = banks_client.get_new_blockhash(&recent_blockhash).await?.0;
+            continue;
+        }
+        return banks_client.process_transaction(transaction).await;
+    }
 }
 
 type TokenTestContext = Token<TokenBanksClientProcessTransaction, Keypair>;
@@ -350,6 +412,15 @@ impl TestContext {
         }))
     }
 
+    /// Move to a later slot, `Clock::slot` is the same for all transactions otherwise.
+    pub async fn warp_to_next_slot(&self) -> UtilResult {
+        let mut ctx = self.ctx.lock().await;
+        // Working bank is at most one slot after the root
+        let slot = ctx.banks_client.get_root_slot().await?;
+        ctx.warp_to_slot(slot + 2)?;
+        Ok(())
+    }
+
     pub async fn get_account(&self, key: Pubkey) -> UtilResult<KeyedAccount> {
         let banks_client = &mut self.ctx.lock().await.banks_client;
         let account = banks_client.get_account(key).await?;
@@ -357,11 +428,30 @@ impl TestContext {
     }
 
     pub async fn gen_serum_dex(self: &Arc<Self>) -> UtilResult<Arc<TestContextDex>> {
+        self.gen_serum_dex_with_authority(None).await
+    }
+
+    /// Permissioned market, `market_authority` is open orders and prune authority.
+    pub async fn gen_permissioned_serum_dex(self: &Arc<Self>) -> UtilResult<Arc<TestContextDex>> {
+        let market_authority = self.rng.new_keypair();
+        self.gen_serum_dex_with_authority(Some(market_authority))
+            .await
+    }
+
+    async fn gen_serum_dex_with_authority(
+        self: &Arc<Self>,
+        market_authority: Option<Keypair>,
+    ) -> UtilResult<Arc<TestContextDex>> {
         // Sizes from:
         // https://github.com/project-serum/serum-dex/blob/1f6d5867019e242a470deed79cddca0d1f15e0a3/dex/crank/src/lib.rs#L1276-L1280
         let payer_pubkey = self.payer.pubkey();
+        let market_len = match market_authority {
+            Some(_) => size_of::<MarketStateV2>(),
+            None => 376,
+        };
         let (market, market_ix) =
-            TestContextDex::create_account_ix(&self.ctx, &self.rng, 376, &payer_pubkey).await?;
+            TestContextDex::create_account_ix(&self.ctx, &self.rng, market_len, &payer_pubkey)
+                .await?;
         let (request_queue, request_queue_ix) =
             TestContextDex::create_account_ix(&self.ctx, &self.rng, 640, &payer_pubkey).await?;
         let (event_queue, event_queue_ix) =
@@ -391,13 +481,15 @@ impl TestContext {
         )
         .await?;
 
-        let authority_pk = None;
-        let prune_authority_pk = None;
+        let authority_pk = market_authority
+            .as_ref()
+            .map(|authority| authority.pubkey());
+        let prune_authority_pk = authority_pk;
         let (vault_signer_nonce, vault_signer) = {
             let mut i = 0;
             loop {
                 assert!(i < 100);
-                if let Ok(pk) = gen_vault_signer_key(i, &market.pubkey(), &serum_saver::dex::ID) {
+                if let Ok(pk) = gen_vault_signer_key(i, &market.pubkey(), &*DEX_PROGRAM) {
                     break (i, pk);
                 }
                 i += 1;
@@ -425,13 +517,13 @@ impl TestContext {
             &mut self.ctx.lock().await.banks_client,
             &[serum_dex::instruction::initialize_market(
                 &market.pubkey(),
-                &serum_saver::dex::ID,
+                &*DEX_PROGRAM,
                 self.coin_token.get_address(),
                 self.pc_token.get_address(),
                 &coin_vault,
                 &pc_vault,
-                authority_pk,
-                prune_authority_pk,
+                authority_pk.as_ref(),
+                prune_authority_pk.as_ref(),
                 &bids.pubkey(),
                 &asks.pubkey(),
                 &request_queue.pubkey(),
@@ -462,10 +554,18 @@ impl TestContext {
             coin_lots,
             pc_lot_size,
             pc_dust_threshold,
+            market_authority,
         }))
     }
 
     pub async fn gen_saver(self: &Arc<Self>) -> UtilResult<Arc<TestContextSaver>> {
+        self.gen_saver_with_dex_program(*DEX_PROGRAM).await
+    }
+
+    pub async fn gen_saver_with_dex_program(
+        self: &Arc<Self>,
+        dex_program: Pubkey,
+    ) -> UtilResult<Arc<TestContextSaver>> {
         let saver = self.rng.new_keypair();
         let (signer, nonce) =
             Pubkey::find_program_address(&[saver.pubkey().as_ref()], &serum_saver::ID);
@@ -477,13 +577,21 @@ impl TestContext {
             &mut self.ctx.lock().await.banks_client,
             &[Instruction::new_with_bytes(
                 serum_saver::ID,
-                &serum_saver::instruction::InitializeSaver { nonce }.data(),
+                &serum_saver::instruction::InitializeSaver {
+                    nonce,
+                    min_fee_tier: FeeTier::Base.into(),
+                }
+                .data(),
                 serum_saver::accounts::InitializeSaver {
                     saver: saver.pubkey(),
                     signer,
 
                     srm_vault,
 
+                    dex_program,
+
+                    authority: self.payer.pubkey(),
+
                     payer: self.payer.pubkey(),
                     system_program: system_program::id(),
                 }
@@ -521,6 +629,8 @@ pub struct TestContextDex {
     pub coin_lots: u64,
     pub pc_lot_size: u64,
     pub pc_dust_threshold: u64,
+    /// Open orders and prune authority of permissioned market.
+    pub market_authority: Option<Keypair>,
 }
 
 impl TestContextDex {
@@ -543,7 +653,7 @@ impl TestContextDex {
                 &new_account_pubkey,
                 rent.minimum_balance(len),
                 len as u64,
-                &serum_saver::dex::ID,
+                &*DEX_PROGRAM,
             ),
         ))
     }
@@ -572,25 +682,54 @@ impl TestContextDex {
         open_orders_ix: Instruction,
         owner: &Keypair,
     ) -> UtilResult {
+        let market_authority = self
+            .market_authority
+            .as_ref()
+            .map(|authority| authority.pubkey());
+        let mut signers = vec![&self.tc.payer, open_orders, owner];
+        signers.extend(self.market_authority.as_ref());
         process_ixs(
             &mut self.tc.ctx.lock().await.banks_client,
             &[
                 open_orders_ix,
                 serum_dex::instruction::init_open_orders(
-                    &serum_saver::dex::ID,
+                    &*DEX_PROGRAM,
                     &open_orders.pubkey(),
                     &owner.pubkey(),
                     &self.market,
-                    None,
+                    market_authority.as_ref(),
                 )?,
             ],
             &self.tc.payer.pubkey(),
-            &[&self.tc.payer, open_orders, owner],
+            &signers,
         )
         .await?;
         Ok(())
     }
 
+    /// Replace open orders and prune authorities of permissioned market. Saver signer can not sign
+    /// `InitOpenOrders` of makers, so market is created with test authority and handed over later.
+    pub async fn set_market_authorities(
+        &self,
+        open_orders_authority: &Pubkey,
+        prune_authority: &Pubkey,
+    ) -> UtilResult {
+        let mut market = self.tc.get_account(self.market).await?;
+        match Market::load(&market.account_info(), &*DEX_PROGRAM)? {
+            Market::V1(_) => panic!("market is not permissioned"),
+            Market::V2(mut state) => {
+                state.open_orders_authority = *open_orders_authority;
+                state.prune_authority = *prune_authority;
+            }
+        }
+        self.tc
+            .ctx
+            .lock()
+            .await
+            .set_account(&self.market, &AccountSharedData::from(market.account));
+        Ok(())
+    }
+
     pub async fn add_liquidity(
         &self,
         open_orders: &Pubkey,
@@ -639,7 +778,7 @@ impl TestContextDex {
                 &spl_token::ID,
                 &spl_token::ID, // Should be `Rent::id()` but this is not used in v0.4.0
                 None,           // srm_account_referral
-                &serum_saver::dex::ID,
+                &*DEX_PROGRAM,
                 side,
                 NonZeroU64::new(limit_price).unwrap(),
                 NonZeroU64::new(coin_qty).unwrap(),
@@ -660,7 +799,7 @@ impl TestContextDex {
         process_ixs(
             &mut self.tc.ctx.lock().await.banks_client,
             &[serum_dex::instruction::consume_events(
-                &serum_saver::dex::ID,
+                &*DEX_PROGRAM,
                 open_orders,
                 &self.market,
                 &self.event_queue,
@@ -674,6 +813,30 @@ impl TestContextDex {
         .await
         .map_err(Into::into)
     }
+
+    /// `ConsumeEventsPermissioned` signed by `market_authority`, which should be prune authority
+    /// of the market at the moment, see `set_market_authorities`.
+    pub async fn consume_events_permissioned(&self, open_orders: &[&Pubkey]) -> UtilResult<()> {
+        let market_authority = self
+            .market_authority
+            .as_ref()
+            .expect("market is not permissioned");
+        process_ixs(
+            &mut self.tc.ctx.lock().await.banks_client,
+            &[serum_saver::dex::consume_events_permissioned(
+                &*DEX_PROGRAM,
+                open_orders,
+                &self.market,
+                &self.event_queue,
+                &market_authority.pubkey(),
+                u16::MAX,
+            )],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer, market_authority],
+        )
+        .await
+        .map_err(Into::into)
+    }
 }
 
 #[derive(Debug)]
@@ -686,9 +849,158 @@ pub struct TestContextSaver {
 }
 
 impl TestContextSaver {
+    pub async fn gen_user_stats(&self, wallet: &Keypair) -> UtilResult<Pubkey> {
+        let (user_stats, nonce) = Pubkey::find_program_address(
+            &[b"stats", self.saver.as_ref(), wallet.pubkey().as_ref()],
+            &serum_saver::ID,
+        );
+
+        process_ixs(
+            &mut self.tc.ctx.lock().await.banks_client,
+            &[Instruction::new_with_bytes(
+                serum_saver::ID,
+                &serum_saver::instruction::InitializeUserStats { nonce }.data(),
+                serum_saver::accounts::InitializeUserStats {
+                    user_stats,
+
+                    saver: self.saver,
+                    wallet: wallet.pubkey(),
+
+                    payer: self.tc.payer.pubkey(),
+                    system_program: system_program::id(),
+                }
+                .to_account_metas(None),
+            )],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer],
+        )
+        .await?;
+
+        Ok(user_stats)
+    }
+
+    /// Approve `delegate` to swap from `wallet` on behalf of `owner`.
+    pub async fn approve(
+        &self,
+        owner: &Keypair,
+        wallet: Pubkey,
+        delegate: &Pubkey,
+        amount: u64,
+    ) -> UtilResult {
+        process_ixs(
+            &mut self.tc.ctx.lock().await.banks_client,
+            &[spl_token::instruction::approve(
+                &spl_token::ID,
+                &wallet,
+                delegate,
+                &owner.pubkey(),
+                &[],
+                amount,
+            )?],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer, owner],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_allowlist_enabled(&self, enabled: bool) -> UtilResult {
+        process_ixs(
+            &mut self.tc.ctx.lock().await.banks_client,
+            &[Instruction::new_with_bytes(
+                serum_saver::ID,
+                &serum_saver::instruction::SetAllowlistEnabled { enabled }.data(),
+                serum_saver::accounts::SetAllowlistEnabled {
+                    saver: self.saver,
+                    authority: self.tc.payer.pubkey(),
+                }
+                .to_account_metas(None),
+            )],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn add_to_allowlist(&self, wallet: &Keypair) -> UtilResult<Pubkey> {
+        let (allowlist_entry, nonce) = Pubkey::find_program_address(
+            &[b"allowlist", self.saver.as_ref(), wallet.pubkey().as_ref()],
+            &serum_saver::ID,
+        );
+
+        process_ixs(
+            &mut self.tc.ctx.lock().await.banks_client,
+            &[Instruction::new_with_bytes(
+                serum_saver::ID,
+                &serum_saver::instruction::AddToAllowlist { nonce }.data(),
+                serum_saver::accounts::AddToAllowlist {
+                    allowlist_entry,
+
+                    saver: self.saver,
+                    authority: self.tc.payer.pubkey(),
+                    wallet: wallet.pubkey(),
+
+                    payer: self.tc.payer.pubkey(),
+                    system_program: system_program::id(),
+                }
+                .to_account_metas(None),
+            )],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer],
+        )
+        .await?;
+
+        Ok(allowlist_entry)
+    }
+
+    pub async fn set_min_fee_tier(&self, min_fee_tier: FeeTier) -> UtilResult {
+        process_ixs(
+            &mut self.tc.ctx.lock().await.banks_client,
+            &[Instruction::new_with_bytes(
+                serum_saver::ID,
+                &serum_saver::instruction::SetMinFeeTier {
+                    min_fee_tier: min_fee_tier.into(),
+                }
+                .data(),
+                serum_saver::accounts::SetMinFeeTier {
+                    saver: self.saver,
+                    authority: self.tc.payer.pubkey(),
+                }
+                .to_account_metas(None),
+            )],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_user_stats(&self, user_stats: Pubkey) -> UtilResult<UserStats> {
+        let account = self.tc.get_account(user_stats).await?;
+        Ok(UserStats::try_deserialize(&mut account.data.as_slice())?)
+    }
+
     pub async fn gen_market(
         self: &Arc<Self>,
         dex: &Arc<TestContextDex>,
+    ) -> UtilResult<TestContextSaverMarket> {
+        self.gen_market_with_mints(
+            dex,
+            *self.tc.coin_token.get_address(),
+            *self.tc.pc_token.get_address(),
+        )
+        .await
+    }
+
+    pub async fn gen_market_with_mints(
+        self: &Arc<Self>,
+        dex: &Arc<TestContextDex>,
+        coin_mint: Pubkey,
+        pc_mint: Pubkey,
     ) -> UtilResult<TestContextSaverMarket> {
         if !Arc::ptr_eq(&self.tc, &dex.tc) {
             return Err("TestContextSaver.tc should be equal to TestContextDex.tc".into());
@@ -696,21 +1008,14 @@ impl TestContextSaver {
 
         let saver_market = self.tc.rng.new_keypair();
 
-        try_join(
-            self.tc
-                .coin_token
-                .get_or_create_associated_account_info(&self.signer),
-            self.tc
-                .pc_token
-                .get_or_create_associated_account_info(&self.signer),
-        )
-        .await?;
-
-        let coin_vault = self
-            .tc
-            .coin_token
-            .get_associated_token_address(&self.signer);
-        let pc_vault = self.tc.pc_token.get_associated_token_address(&self.signer);
+        let (coin_vault, coin_vault_nonce) = Pubkey::find_program_address(
+            &[saver_market.pubkey().as_ref(), coin_mint.as_ref()],
+            &serum_saver::ID,
+        );
+        let (pc_vault, pc_vault_nonce) = Pubkey::find_program_address(
+            &[saver_market.pubkey().as_ref(), pc_mint.as_ref()],
+            &serum_saver::ID,
+        );
 
         let (open_orders, open_orders_ix) = dex.gen_open_orders_create().await?;
 
@@ -720,24 +1025,30 @@ impl TestContextSaver {
                 open_orders_ix,
                 Instruction::new_with_bytes(
                     serum_saver::ID,
-                    &serum_saver::instruction::InitializeMarket {}.data(),
+                    &serum_saver::instruction::InitializeMarket {
+                        coin_vault_nonce,
+                        pc_vault_nonce,
+                    }
+                    .data(),
                     serum_saver::accounts::InitializeMarket {
                         saver_market: saver_market.pubkey(),
 
                         saver: self.saver,
                         signer: self.signer,
 
-                        coin_mint: *self.tc.coin_token.get_address(),
+                        coin_mint,
                         coin_vault,
-                        pc_mint: *self.tc.pc_token.get_address(),
+                        pc_mint,
                         pc_vault,
 
-                        dex_program: serum_saver::dex::ID,
+                        dex_program: *DEX_PROGRAM,
                         dex_market: dex.market,
                         dex_open_orders: open_orders.pubkey(),
 
                         payer: self.tc.payer.pubkey(),
                         system_program: system_program::id(),
+                        spl_token_program: serum_saver::token::ID,
+                        rent: sysvar::rent::ID,
                     }
                     .to_account_metas(None),
                 ),
@@ -760,7 +1071,7 @@ impl TestContextSaver {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TestContextSaverMarket {
     pub tc: Arc<TestContext>,
     pub dex: Arc<TestContextDex>,
@@ -773,70 +1084,445 @@ pub struct TestContextSaverMarket {
 }
 
 impl TestContextSaverMarket {
-    pub async fn buy(&self, limit_price: u64, max_coin_qty: u64, owner: &Keypair) -> UtilResult {
-        self.swap(Side::Bid, limit_price, max_coin_qty, owner).await
+    pub async fn get_state(&self) -> UtilResult<SaverMarket> {
+        let account = self.tc.get_account(self.saver_market).await?;
+        Ok(SaverMarket::try_deserialize(&mut account.data.as_slice())?)
     }
 
-    pub async fn sell(&self, limit_price: u64, max_coin_qty: u64, owner: &Keypair) -> UtilResult {
-        self.swap(Side::Ask, limit_price, max_coin_qty, owner).await
+    pub async fn gen_user_volume(&self, wallet: &Keypair) -> UtilResult<Pubkey> {
+        let (user_volume, nonce) = Pubkey::find_program_address(
+            &[
+                b"volume",
+                self.saver_market.as_ref(),
+                wallet.pubkey().as_ref(),
+            ],
+            &serum_saver::ID,
+        );
+
+        process_ixs(
+            &mut self.tc.ctx.lock().await.banks_client,
+            &[Instruction::new_with_bytes(
+                serum_saver::ID,
+                &serum_saver::instruction::InitializeUserVolume { nonce }.data(),
+                serum_saver::accounts::InitializeUserVolume {
+                    user_volume,
+
+                    saver_market: self.saver_market,
+                    wallet: wallet.pubkey(),
+
+                    payer: self.tc.payer.pubkey(),
+                    system_program: system_program::id(),
+                }
+                .to_account_metas(None),
+            )],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer],
+        )
+        .await?;
+
+        Ok(user_volume)
     }
 
-    pub async fn swap(
+    pub async fn set_limits(
         &self,
-        side: Side,
-        limit_price: u64,
-        max_coin_qty: u64,
-        owner: &Keypair,
+        max_notional_per_swap: u64,
+        max_swaps_per_slot: u64,
+        max_daily_volume_per_user: u64,
     ) -> UtilResult {
-        let max_native_pc_qty = limit_price * max_coin_qty * self.dex.pc_lot_size;
-        let max_native_pc_qty_including_fees = ((max_native_pc_qty as f64) * 1.0022) as u64;
+        process_ixs(
+            &mut self.tc.ctx.lock().await.banks_client,
+            &[Instruction::new_with_bytes(
+                serum_saver::ID,
+                &serum_saver::instruction::SetMarketLimits {
+                    max_notional_per_swap,
+                    max_swaps_per_slot,
+                    max_daily_volume_per_user,
+                }
+                .data(),
+                serum_saver::accounts::SetMarketLimits {
+                    saver_market: self.saver_market,
 
-        let tc = &self.dex.tc;
+                    saver: self.tcs.saver,
+                    authority: self.tc.payer.pubkey(),
+                }
+                .to_account_metas(None),
+            )],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_oracle(
+        &self,
+        oracle: Pubkey,
+        oracle_band_bps: u64,
+        max_oracle_age_slots: u64,
+    ) -> UtilResult {
         process_ixs(
-            &mut self.dex.tc.ctx.lock().await.banks_client,
+            &mut self.tc.ctx.lock().await.banks_client,
             &[Instruction::new_with_bytes(
                 serum_saver::ID,
-                &serum_saver::instruction::Swap {
-                    side: side.into(),
-                    limit_price,
-                    max_coin_qty,
-                    max_native_pc_qty_including_fees,
+                &serum_saver::instruction::SetMarketOracle {
+                    oracle,
+                    oracle_band_bps,
+                    max_oracle_age_slots,
                 }
                 .data(),
-                serum_saver::accounts::Swap {
+                serum_saver::accounts::SetMarketOracle {
+                    saver_market: self.saver_market,
+
+                    saver: self.tcs.saver,
+                    authority: self.tc.payer.pubkey(),
+                }
+                .to_account_metas(None),
+            )],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_crank_tip(&self, crank_tip: u64) -> UtilResult {
+        process_ixs(
+            &mut self.tc.ctx.lock().await.banks_client,
+            &[Instruction::new_with_bytes(
+                serum_saver::ID,
+                &serum_saver::instruction::SetCrankTip { crank_tip }.data(),
+                serum_saver::accounts::SetCrankTip {
+                    saver_market: self.saver_market,
+
+                    saver: self.tcs.saver,
+                    authority: self.tc.payer.pubkey(),
+                }
+                .to_account_metas(None),
+            )],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn crank(&self, tip_wallet: &Pubkey) -> UtilResult {
+        process_ixs(
+            &mut self.tc.ctx.lock().await.banks_client,
+            &[Instruction::new_with_bytes(
+                serum_saver::ID,
+                &serum_saver::instruction::Crank { limit: u16::MAX }.data(),
+                serum_saver::accounts::Crank {
                     saver: self.tcs.saver,
                     signer: self.tcs.signer,
-                    srm_vault: self.tcs.srm_vault,
 
                     saver_market: self.saver_market,
 
-                    coin_vault: self.coin_vault,
                     pc_vault: self.pc_vault,
-
-                    coin_wallet: tc.coin_token.get_associated_token_address(&owner.pubkey()),
-                    pc_wallet: tc.pc_token.get_associated_token_address(&owner.pubkey()),
-                    wallet_signer: owner.pubkey(),
+                    tip_wallet: *tip_wallet,
 
                     market: self.dex.market,
                     open_orders: self.open_orders,
-                    request_queue: self.dex.request_queue,
                     event_queue: self.dex.event_queue,
-                    bids: self.dex.bids,
-                    asks: self.dex.asks,
+
+                    dex_program: *DEX_PROGRAM,
+                    spl_token_program: spl_token::ID,
+                }
+                .to_account_metas(None),
+            )],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn settle(&self) -> UtilResult {
+        process_ixs(
+            &mut self.tc.ctx.lock().await.banks_client,
+            &[Instruction::new_with_bytes(
+                serum_saver::ID,
+                &serum_saver::instruction::Settle {}.data(),
+                serum_saver::accounts::Settle {
+                    saver: self.tcs.saver,
+                    signer: self.tcs.signer,
+
+                    saver_market: self.saver_market,
+
+                    coin_vault: self.coin_vault,
+                    pc_vault: self.pc_vault,
+
+                    market: self.dex.market,
+                    open_orders: self.open_orders,
                     dex_coin_vault: self.dex.coin_vault,
                     dex_pc_vault: self.dex.pc_vault,
                     dex_vault_signer: self.dex.vault_signer,
 
-                    dex_program: serum_saver::dex::ID,
-                    spl_token_program: serum_saver::token::ID,
+                    dex_program: *DEX_PROGRAM,
+                    spl_token_program: spl_token::ID,
+                }
+                .to_account_metas(None),
+            )],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Withdraw market fees to associated wallets of `owner`, signed by saver authority `authority`.
+    pub async fn withdraw_fees(
+        &self,
+        authority: &Keypair,
+        owner: &Pubkey,
+        coin_amount: u64,
+        pc_amount: u64,
+    ) -> UtilResult {
+        process_ixs(
+            &mut self.tc.ctx.lock().await.banks_client,
+            &[Instruction::new_with_bytes(
+                serum_saver::ID,
+                &serum_saver::instruction::WithdrawFees {
+                    coin_amount,
+                    pc_amount,
+                }
+                .data(),
+                serum_saver::accounts::WithdrawFees {
+                    saver: self.tcs.saver,
+                    signer: self.tcs.signer,
+                    authority: authority.pubkey(),
+
+                    saver_market: self.saver_market,
+
+                    coin_vault: self.coin_vault,
+                    pc_vault: self.pc_vault,
+
+                    coin_wallet: self.tc.coin_token.get_associated_token_address(owner),
+                    pc_wallet: self.tc.pc_token.get_associated_token_address(owner),
+
+                    spl_token_program: spl_token::ID,
                 }
                 .to_account_metas(None),
             )],
+            &self.tc.payer.pubkey(),
+            &[&self.tc.payer, authority],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Same market, but instructions pass another `open_orders`.
+    pub fn with_open_orders(&self, open_orders: Pubkey) -> Self {
+        Self {
+            open_orders,
+            ..self.clone()
+        }
+    }
+
+    pub async fn buy(&self, limit_price: u64, max_coin_qty: u64, owner: &Keypair) -> UtilResult {
+        self.swap(Side::Bid, limit_price, max_coin_qty, owner).await
+    }
+
+    pub async fn sell(&self, limit_price: u64, max_coin_qty: u64, owner: &Keypair) -> UtilResult {
+        self.swap(Side::Ask, limit_price, max_coin_qty, owner).await
+    }
+
+    pub async fn swap(
+        &self,
+        side: Side,
+        limit_price: u64,
+        max_coin_qty: u64,
+        owner: &Keypair,
+    ) -> UtilResult {
+        self.swap_with_stats(side, limit_price, max_coin_qty, owner, None)
+            .await
+    }
+
+    pub async fn swap_with_stats(
+        &self,
+        side: Side,
+        limit_price: u64,
+        max_coin_qty: u64,
+        owner: &Keypair,
+        user_stats: Option<Pubkey>,
+    ) -> UtilResult {
+        self.swap_signed(
+            side,
+            limit_price,
+            max_coin_qty,
+            &owner.pubkey(),
+            owner,
+            &user_stats.into_iter().collect::<Vec<_>>(),
+        )
+        .await
+    }
+
+    /// Swap from wallets of `owner` signed by `wallet_signer`, which can be a delegate.
+    /// `remaining_accounts` are `UserStats` and `AllowlistEntry`.
+    pub async fn swap_signed(
+        &self,
+        side: Side,
+        limit_price: u64,
+        max_coin_qty: u64,
+        owner: &Pubkey,
+        wallet_signer: &Keypair,
+        remaining_accounts: &[Pubkey],
+    ) -> UtilResult {
+        let max_native_pc_qty = limit_price * max_coin_qty * self.dex.pc_lot_size;
+        let max_native_pc_qty_including_fees = ((max_native_pc_qty as f64) * 1.0022) as u64;
+
+        let tc = &self.dex.tc;
+        let mut ix = Instruction::new_with_bytes(
+            serum_saver::ID,
+            &serum_saver::instruction::Swap {
+                side: side.into(),
+                limit_price,
+                max_coin_qty,
+                max_native_pc_qty_including_fees,
+                limit: u16::MAX,
+                max_price_impact_bps: 0,
+                self_trade_behavior: SelfTradeBehavior::AbortTransaction.into(),
+                client_order_id: 0,
+            }
+            .data(),
+            serum_saver::accounts::Swap {
+                saver: self.tcs.saver,
+                signer: self.tcs.signer,
+                srm_vault: self.tcs.srm_vault,
+
+                saver_market: self.saver_market,
+
+                coin_vault: self.coin_vault,
+                pc_vault: self.pc_vault,
+
+                coin_wallet: tc.coin_token.get_associated_token_address(owner),
+                pc_wallet: tc.pc_token.get_associated_token_address(owner),
+                wallet_signer: wallet_signer.pubkey(),
+
+                market: self.dex.market,
+                open_orders: self.open_orders,
+                request_queue: self.dex.request_queue,
+                event_queue: self.dex.event_queue,
+                bids: self.dex.bids,
+                asks: self.dex.asks,
+                dex_coin_vault: self.dex.coin_vault,
+                dex_pc_vault: self.dex.pc_vault,
+                dex_vault_signer: self.dex.vault_signer,
+
+                dex_program: *DEX_PROGRAM,
+                spl_token_program: serum_saver::token::ID,
+            }
+            .to_account_metas(None),
+        );
+        for account in remaining_accounts {
+            ix.accounts.push(AccountMeta::new(*account, false));
+        }
+
+        process_ixs(
+            &mut self.dex.tc.ctx.lock().await.banks_client,
+            &[ix],
             &self.dex.tc.payer.pubkey(),
-            &[&self.dex.tc.payer, owner],
+            &[&self.dex.tc.payer, wallet_signer],
         )
         .await?;
 
         Ok(())
     }
 }
+
+pub const BID_PRICE: u64 = 198;
+pub const ASK_PRICE: u64 = 202;
+
+/// Accounts created by `setup`, Alice is maker with `maker_open_orders`.
+pub struct Playground {
+    pub tc: Arc<TestContext>,
+    pub dex: Arc<TestContextDex>,
+    pub saver: Arc<TestContextSaver>,
+    pub saver_market: TestContextSaverMarket,
+    pub maker_open_orders: Pubkey,
+}
+
+/// Market with resting bid and ask of Alice, saver market on it and Bob with coin and pc.
+pub async fn setup() -> UtilResult<Playground> {
+    // let tc = TestContext::new(None).await?;
+    let tc = TestContext::new(Some("warn")).await?;
+    let dex = tc.gen_serum_dex().await?;
+    setup_on_dex(tc, dex).await
+}
+
+/// Same as `setup`, but market is permissioned and saver signer is its open orders and prune
+/// authority.
+pub async fn setup_permissioned() -> UtilResult<Playground> {
+    let tc = TestContext::new(Some("warn")).await?;
+    let dex = tc.gen_permissioned_serum_dex().await?;
+    setup_on_dex(tc, dex).await
+}
+
+pub async fn setup_on_dex(
+    tc: Arc<TestContext>,
+    dex: Arc<TestContextDex>,
+) -> UtilResult<Playground> {
+    // Maker
+    let maker_open_orders = dex.gen_open_orders(&tc.alice).await?;
+    dex.add_liquidity(
+        &maker_open_orders,
+        &tc.alice,
+        Side::Bid,
+        BID_PRICE,
+        100 * dex.coin_lots,
+    )
+    .await?;
+    dex.add_liquidity(
+        &maker_open_orders,
+        &tc.alice,
+        Side::Ask,
+        ASK_PRICE,
+        100 * dex.coin_lots,
+    )
+    .await?;
+
+    let coin_vault = tc.coin_token.get_associated_token_address(&tc.bob.pubkey());
+    tc.coin_token
+        .mint_to(
+            &coin_vault,
+            &tc.coin_token_authority,
+            1_000_000 * u64::pow(10, tc.coin_token_decimals as u32),
+        )
+        .await?;
+    let pc_vault = tc.pc_token.get_associated_token_address(&tc.bob.pubkey());
+    tc.pc_token
+        .mint_to(
+            &pc_vault,
+            &tc.pc_token_authority,
+            1_000_000 * u64::pow(10, tc.pc_token_decimals as u32),
+        )
+        .await?;
+
+    let saver = tc.gen_saver().await?;
+    if dex.market_authority.is_some() {
+        dex.set_market_authorities(&saver.signer, &saver.signer)
+            .await?;
+    }
+    // tc.srm_token
+    //     .mint_to(
+    //         &saver.srm_vault,
+    //         &tc.srm_token_authhority,
+    //         200 * u64::pow(10, tc.srm_token_decimals as u32),
+    //     )
+    //     .await?;
+    let saver_market = saver.gen_market(&dex).await?;
+
+    Ok(Playground {
+        tc,
+        dex,
+        saver,
+        saver_market,
+        maker_open_orders,
+    })
+}